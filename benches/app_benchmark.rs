@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use simulation::{init, App, Settings};
+use simulation::{init, Algorithm, App, Error, Settings};
 
 const SIMPLE_SIM: &str = r#"{
     "Lander": {
@@ -61,6 +61,7 @@ fn light_settings() -> Settings {
         population_size: 30,
         elitism: 0.15,
         mutation_prob: 0.01,
+        ..Default::default()
     }
 }
 
@@ -70,6 +71,35 @@ fn hard_settings() -> Settings {
         chromosome_size: 160,
         elitism: 0.15,
         mutation_prob: 0.01,
+        ..Default::default()
+    }
+}
+
+fn light_cma_es_settings() -> Settings {
+    Settings {
+        algorithm: Algorithm::CmaEs,
+        ..light_settings()
+    }
+}
+
+fn hard_cma_es_settings() -> Settings {
+    Settings {
+        algorithm: Algorithm::CmaEs,
+        ..hard_settings()
+    }
+}
+
+fn light_particle_swarm_settings() -> Settings {
+    Settings {
+        algorithm: Algorithm::ParticleSwarm,
+        ..light_settings()
+    }
+}
+
+fn light_annealing_settings() -> Settings {
+    Settings {
+        algorithm: Algorithm::SimulatedAnnealing,
+        ..light_settings()
     }
 }
 
@@ -93,7 +123,7 @@ fn complicated_sim() -> (simulation::LanderState, simulation::Terrain) {
 fn to_app(
     (lander_state, terrain): (simulation::LanderState, simulation::Terrain),
     settings: Settings,
-) -> Result<App, String> {
+) -> Result<App, Error> {
     App::try_new(lander_state, terrain, settings)
 }
 
@@ -124,6 +154,26 @@ pub fn run_benchmark(c: &mut Criterion) {
     bench!(run_next_population, complicated_sim, light_settings);
     bench!(run_next_population, simple_sim, hard_settings);
     bench!(run_next_population, complicated_sim, hard_settings);
+    bench!(run_next_population, simple_sim, light_cma_es_settings);
+    bench!(run_next_population, complicated_sim, light_cma_es_settings);
+    bench!(run_next_population, simple_sim, hard_cma_es_settings);
+    bench!(run_next_population, complicated_sim, hard_cma_es_settings);
+    bench!(
+        run_next_population,
+        simple_sim,
+        light_particle_swarm_settings
+    );
+    bench!(
+        run_next_population,
+        complicated_sim,
+        light_particle_swarm_settings
+    );
+    bench!(run_next_population, simple_sim, light_annealing_settings);
+    bench!(
+        run_next_population,
+        complicated_sim,
+        light_annealing_settings
+    );
 }
 
 criterion_group!(benches, run_benchmark);
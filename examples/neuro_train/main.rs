@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use simulation::{fly_scenario, init::json, Error, NeuroEvolution, NeuroSettings, SolverError};
+
+/// Evolves a neural network landing policy on training simulations
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// Training simulation json file paths
+    #[arg(long, value_name = "FILE", required = true)]
+    sim: Vec<PathBuf>,
+
+    /// Simulation json file paths the evolved policy is validated on
+    #[arg(long, value_name = "FILE")]
+    validate: Vec<PathBuf>,
+
+    #[arg(long, short, default_value = "300")]
+    generations: usize,
+
+    #[arg(long, default_value = "100")]
+    population_size: usize,
+
+    /// Comma separated sizes of hidden layers
+    #[arg(long, value_delimiter = ',', default_value = "8")]
+    hidden: Vec<usize>,
+
+    /// File the best network weights are saved to
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+
+    let scenarios = cli
+        .sim
+        .iter()
+        .map(json::parse_sim)
+        .collect::<Result<Vec<_>, _>>()?;
+    let n_scenarios = scenarios.len();
+    let mut evolution = NeuroEvolution::try_new(
+        NeuroSettings {
+            population_size: cli.population_size,
+            hidden_layers: cli.hidden,
+            ..Default::default()
+        },
+        scenarios,
+    )?;
+
+    for generation in 0..cli.generations {
+        let summary = evolution.next_generation()?;
+        if generation % 10 == 0 || summary.correct_landings == n_scenarios {
+            println!(
+                "Generation {generation}: best fitness {:.3}, correct landings {}/{n_scenarios}",
+                summary.best_fitness, summary.correct_landings
+            );
+        }
+        if summary.correct_landings == n_scenarios {
+            break;
+        }
+    }
+
+    let mut best = evolution
+        .best()
+        .ok_or(SolverError::EmptyPopulation)?
+        .clone();
+    for path in &cli.validate {
+        let (lander, terrain) = json::parse_sim(path)?;
+        let (_, landing) = fly_scenario(&mut best, lander, terrain)?;
+        println!("Validation {path:?}: {landing:?}");
+    }
+    if let Some(output) = cli.output {
+        json::save_network(&best, &output)?;
+        println!("Saved weights to {output:?}");
+    }
+    Ok(())
+}
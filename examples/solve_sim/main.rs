@@ -1,7 +1,27 @@
-use std::path::PathBuf;
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use clap::Parser;
-use simulation::{App, Chromosome, FlightState, Landing};
+use clap::{Parser, ValueEnum};
+use simulation::{
+    init::json, solve_portfolio, App, AppBuilder, Autopilot, Controller, GenerationReport,
+    MpcController, MpcSettings, Solution, WaypointPilot,
+};
+
+/// Steps after which a controlled flight is considered failed
+const CONTROLLER_MAX_STEPS: usize = 1000;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ControllerKind {
+    /// Rule-based autopilot
+    Autopilot,
+    /// Model predictive control
+    Mpc,
+    /// Autopilot flying through the corridor planned with the `Planner` settings
+    Waypoints,
+}
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -14,25 +34,243 @@ struct Cli {
     #[arg(long, value_name = "FILE")]
     sim: PathBuf,
 
-    /// Maximal number of iterations
-    #[arg(long, short, default_value = "1000")]
-    iterations_max: usize,
+    /// Time budget for solving in milliseconds
+    #[arg(long, short, default_value = "60000")]
+    time_limit_ms: u64,
+
+    /// After finding a solution keep optimizing remaining fuel for that many milliseconds
+    #[arg(long, value_name = "MS")]
+    fuel_time_limit_ms: Option<u64>,
+
+    /// Fly closed-loop controllers first and print their landings for comparison
+    #[arg(long, value_enum)]
+    controller: Vec<ControllerKind>,
+
+    /// Resume from this checkpoint if it exists and periodically save progress to it
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<PathBuf>,
+
+    /// Time between checkpoint saves in milliseconds
+    #[arg(long, value_name = "MS", default_value = "10000")]
+    checkpoint_interval_ms: u64,
+
+    /// Write fitness and diversity of every generation to this CSV file
+    #[arg(long, value_name = "FILE")]
+    diversity_csv: Option<PathBuf>,
+
+    /// Print statistics of every generation
+    #[arg(long)]
+    progress: bool,
+
+    /// Solve with that many independent solvers on separate threads, stopping all of them at the
+    /// first correct landing
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "1",
+        conflicts_with = "checkpoint"
+    )]
+    portfolio: usize,
 }
 
-fn main() -> Result<(), String> {
+fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
+    if cli.portfolio > 1 {
+        return solve_with_portfolio(&cli, Duration::from_millis(cli.time_limit_ms));
+    }
+
+    let mut app = match &cli.checkpoint {
+        Some(checkpoint) if checkpoint.exists() => {
+            let app = App::try_from_checkpoint_file(checkpoint)?;
+            println!(
+                "Resumed from {checkpoint:?} at generation {}",
+                app.get_population_id()
+            );
+            app
+        }
+        _ => App::try_from_files(cli.sim, cli.settings)?,
+    };
+    if cli.progress {
+        app.add_observer(print_report);
+    }
+
+    for kind in cli.controller {
+        let mut controller: Box<dyn Controller> = match kind {
+            ControllerKind::Autopilot => Box::new(Autopilot::default()),
+            ControllerKind::Mpc => Box::new(MpcController::try_with_default_model(
+                app.get_terrain(),
+                MpcSettings::default(),
+            )?),
+            ControllerKind::Waypoints => Box::new(WaypointPilot::new(
+                app.get_corridor()
+                    .ok_or("Waypoints controller requires Planner settings")?
+                    .to_vec(),
+            )),
+        };
+        let (commands, history, landing) = app.fly(&mut controller, CONTROLLER_MAX_STEPS)?;
+        let fuel = history.last_state().map_or(0, |lander| lander.fuel);
+        println!(
+            "{kind:?}: {landing:?} after {} steps, fuel left {fuel}",
+            commands.angles.len()
+        );
+    }
 
-    let mut app = App::try_from_files(cli.sim, cli.settings)?;
+    let budget = Duration::from_millis(cli.time_limit_ms);
+    let solution = match &cli.checkpoint {
+        Some(checkpoint) => solve_with_checkpoints(
+            &mut app,
+            budget,
+            checkpoint,
+            Duration::from_millis(cli.checkpoint_interval_ms),
+        )?,
+        None => app.solve_for(budget)?,
+    };
+    if let Some(diversity_csv) = &cli.diversity_csv {
+        write_diversity_csv(&app, diversity_csv)?;
+    }
+    let stats = &solution.stats;
+    println!("Chromosome horizon: {} genes", app.get_horizon());
+    let cache = app.get_cache_stats();
+    println!(
+        "Simulation cache: {} full and {} prefix hits, {} steps reused, {} simulated",
+        cache.full_hits, cache.prefix_hits, cache.reused_steps, cache.simulated_steps
+    );
+    if solution.landed {
+        println!(
+            "Found solution in {} generation ({} simulations, {:?})",
+            stats.generations - 1,
+            stats.simulations,
+            stats.elapsed
+        );
+        let replay = app.replay(&solution.chromosome.commands(), CONTROLLER_MAX_STEPS)?;
+        println!("Replayed solution: {:?}", replay.landing);
+        if let Some(fuel_time_limit_ms) = cli.fuel_time_limit_ms {
+            optimize_fuel(&mut app, Duration::from_millis(fuel_time_limit_ms))?;
+        }
+        Ok(())
+    } else {
+        Err(format!(
+            "Time limit reached ({:?}) after {} generations ({} simulations)",
+            stats.elapsed, stats.generations, stats.simulations
+        )
+        .into())
+    }
+}
+
+fn solve_with_portfolio(cli: &Cli, budget: Duration) -> Result<(), Box<dyn Error>> {
+    let (initial_lander_state, terrain) = json::parse_sim(&cli.sim)?;
+    let builder = AppBuilder::new(initial_lander_state, terrain)
+        .with_settings(json::parse_settings(&cli.settings)?);
+    let portfolio = solve_portfolio(vec![builder; cli.portfolio], budget)?;
+    for (member, stats) in portfolio.member_stats.iter().enumerate() {
+        println!(
+            "Member {member}: {} generations ({} simulations, {:?})",
+            stats.generations, stats.simulations, stats.elapsed
+        );
+    }
+    if portfolio.solution.landed {
+        println!(
+            "Member {} found solution in {:?}",
+            portfolio.member, portfolio.elapsed
+        );
+        Ok(())
+    } else {
+        Err(format!(
+            "Time limit reached ({:?}), best solution by member {}",
+            portfolio.elapsed, portfolio.member
+        )
+        .into())
+    }
+}
 
-    for i in 0..cli.iterations_max {
-        if let Some(_) = app.run()? {
-            println!("Found solution in {i} generation");
-            return Ok(());
+/// Solves in chunks of `interval`, saving a checkpoint after each of them
+fn solve_with_checkpoints(
+    app: &mut App,
+    budget: Duration,
+    checkpoint: &Path,
+    interval: Duration,
+) -> Result<Solution, Box<dyn Error>> {
+    let mut total = app.solve_for(interval.min(budget))?;
+    loop {
+        app.save_checkpoint(checkpoint)?;
+        let remaining = budget.saturating_sub(total.stats.elapsed);
+        if total.landed || remaining.is_zero() {
+            return Ok(total);
         }
-        app.next_population()?;
+        let Solution {
+            chromosome,
+            landed,
+            fitness,
+            stats,
+        } = app.solve_for(interval.min(remaining))?;
+        total.stats.generations += stats.generations;
+        total.stats.simulations += stats.simulations;
+        total.stats.elapsed += stats.elapsed;
+        total.chromosome = chromosome;
+        total.landed = landed;
+        total.fitness = fitness;
+    }
+}
+
+fn print_report(report: &GenerationReport) {
+    let landings = &report.landings;
+    let touchdown = report.best_touchdown.as_ref().map_or(String::new(), |t| {
+        format!(
+            ", best touchdown margins vy {:.1} vx {:.1} tilt {:.0}",
+            t.vertical_speed, t.horizontal_speed, t.tilt
+        )
+    });
+    println!(
+        "Generation {}: fitness best {:.3} mean {:.3} median {:.3} std {:.3}, \
+        landings {}/{} correct{touchdown}, {:?} simulating, {:?} solving",
+        report.generation,
+        report.best_fitness,
+        report.mean_fitness,
+        report.median_fitness,
+        report.std_fitness,
+        landings.correct,
+        landings.total(),
+        report.simulation_time,
+        report.solver_time
+    );
+}
+
+fn write_diversity_csv(app: &App, path: &Path) -> Result<(), Box<dyn Error>> {
+    let history = app.get_fitness_history();
+    let mut csv = String::from(
+        "generation,best_fitness,mean_fitness,gene_distance,mean_entropy,touchdown_spread\n",
+    );
+    for (generation, ((best, mean), diversity)) in history
+        .best()
+        .iter()
+        .zip(history.mean())
+        .zip(app.get_diversity_history())
+        .enumerate()
+    {
+        csv += &format!(
+            "{generation},{best},{mean},{},{},{}\n",
+            diversity.gene_distance,
+            diversity.mean_entropy(),
+            diversity.touchdown_spread
+        );
+    }
+    std::fs::write(path, csv).map_err(|e| format!("Failed to write file {path:?}: {e}"))?;
+    Ok(())
+}
+
+fn optimize_fuel(app: &mut App, budget: Duration) -> Result<(), Box<dyn Error>> {
+    let optimization = app
+        .optimize_fuel_for(budget)?
+        .ok_or("Lost the solution while optimizing fuel")?;
+    for improvement in &optimization.improvements {
+        println!(
+            "Generation {}: fuel left {} ({:?})",
+            improvement.generation, improvement.fuel, improvement.elapsed
+        );
     }
-    Err(format!(
-        "Maximal iterations count reached ({})",
-        cli.iterations_max
-    ))
+    println!(
+        "Best fuel left {} after {} generations ({} simulations)",
+        optimization.fuel, optimization.stats.generations, optimization.stats.simulations
+    );
+    Ok(())
 }
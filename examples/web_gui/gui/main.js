@@ -60,6 +60,22 @@ const routeGridOptions = {
 const routeGrid = document.querySelector('#routeGrid');
 let routeGridApi = agGrid.createGrid(routeGrid, routeGridOptions);
 
+const paretoGridOptions = {
+    rowData: [
+    ],
+    onRowClicked: myRowClickedHandler,
+    columnDefs: [
+        { field: "Id" },
+        { headerName: "Landing Distance", field: "landing_distance" },
+        { headerName: "Speed Error", field: "speed_error" },
+        { headerName: "Angle Error", field: "angle_error" },
+        { headerName: "Fuel Used", field: "fuel_used" },
+    ]
+};
+
+const paretoGrid = document.querySelector('#paretoGrid');
+let paretoGridApi = agGrid.createGrid(paretoGrid, paretoGridOptions);
+
 const scaling = 0.2;
 const maxY = 3000;
 const maxX = 7000;
@@ -98,6 +114,7 @@ async function handleNext() {
     });
     redraw();
     drawFitnessTable(currentPopulation);
+    await fetchDataAndHandleResponse('diversity', drawDiversity);
 }
 
 let nextButton = document.getElementById("next_button");
@@ -120,6 +137,27 @@ reset_button.onclick = async () => {
     drawFitnessTable(currentPopulation);
 };
 
+let checkpoint_button = document.getElementById("checkpoint_button");
+checkpoint_button.onclick = async () => {
+    try_clear_run();
+    await fetchData('checkpoint', {
+        method: 'PUT',
+    }).catch((error) => console.log(error));
+};
+
+let restore_button = document.getElementById("restore_button");
+restore_button.onclick = async () => {
+    try_clear_run();
+    await fetchData('restore', {
+        method: 'PUT',
+    });
+    await fetchDataAndHandleResponse('population', (data) => {
+        currentPopulation = data;
+    });
+    redraw();
+    drawFitnessTable(currentPopulation);
+};
+
 let reset_filter_button = document.getElementById("reset_filter");
 reset_filter_button.onclick = async () => {
     try_clear_run();
@@ -129,6 +167,17 @@ reset_filter_button.onclick = async () => {
 };
 
 
+let pareto_button = document.getElementById("pareto_button");
+pareto_button.onclick = async () => {
+    try_clear_run();
+    await fetchDataAndHandleResponse('pareto', (front) => {
+        const ids = new Set(front.map((member) => member.id));
+        paretoGridApi.setGridOption('rowData', front.map((member) => { return { Id: member.id, ...member }; }));
+        routeFilter = (_, i) => ids.has(i);
+    });
+    redraw();
+};
+
 let run_until_solution_button = document.getElementById("until_solution_found");
 const RUN_UNTIL_INTERVAL_MS = 300;
 run_until_solution_button.onclick = async () => {
@@ -136,6 +185,34 @@ run_until_solution_button.onclick = async () => {
     run_switch = setInterval(handleNext, RUN_UNTIL_INTERVAL_MS);
 }
 
+// plots diversity of every generation, each metric scaled to the canvas height
+function drawDiversity(diversity) {
+    const diversityCanvas = document.getElementById("diversityCanvas");
+    const diversityCtx = diversityCanvas.getContext("2d");
+    diversityCtx.clearRect(0, 0, diversityCanvas.width, diversityCanvas.height);
+    const generations = diversity.generations;
+    if (generations.length < 2) {
+        return;
+    }
+    const plot = (values, color) => {
+        diversityCtx.strokeStyle = color;
+        diversityCtx.beginPath();
+        values.forEach((v, i) => {
+            const x = i / (values.length - 1) * diversityCanvas.width;
+            const y = (1 - v) * diversityCanvas.height;
+            if (i === 0) {
+                diversityCtx.moveTo(x, y);
+            } else {
+                diversityCtx.lineTo(x, y);
+            }
+        });
+        diversityCtx.stroke();
+    };
+    plot(generations.map((g) => g.gene_distance), "orange");
+    plot(generations.map((g) => g.mean_entropy), "cyan");
+    plot(generations.map((g) => g.touchdown_spread / maxX), "magenta");
+}
+
 function printStats(population) {
     const routes_sizes = Array.from(population['routes'], (r) => r['positions'].length);
     const mean = routes_sizes.reduce((accumulator, currentValue) => accumulator + currentValue, 0) / routes_sizes.length;
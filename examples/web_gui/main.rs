@@ -1,7 +1,7 @@
 use std::sync::{Arc, Mutex};
 
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::Json,
     routing::{get, put},
@@ -10,9 +10,12 @@ use axum::{
 use serde::Serialize;
 use serde_json::Value;
 use simulation::{App, LanderState};
-use std::env;
+use std::{env, path::PathBuf, time::Duration};
 use tower_http::cors::CorsLayer;
 
+/// Checkpoint file used when no path is given after the settings path
+const DEFAULT_CHECKPOINT_FILE: &str = "checkpoint.json";
+
 // https://docs.rs/axum/latest/axum/index.html#using-the-state-extractor
 
 #[derive(Serialize, Default)]
@@ -79,9 +82,48 @@ struct Population {
     commands_accumulated: Vec<Commands>,
 }
 
+#[derive(Serialize)]
+struct ParetoMember {
+    id: usize,
+    landing_distance: f64,
+    speed_error: f64,
+    angle_error: f64,
+    fuel_used: f64,
+}
+
+impl From<(usize, simulation::Objectives)> for ParetoMember {
+    fn from((id, objectives): (usize, simulation::Objectives)) -> Self {
+        Self {
+            id,
+            landing_distance: objectives.landing_distance,
+            speed_error: objectives.speed_error,
+            angle_error: objectives.angle_error,
+            fuel_used: objectives.fuel_used,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GenerationDiversity {
+    best_fitness: f64,
+    mean_fitness: f64,
+    gene_distance: f64,
+    mean_entropy: f64,
+    touchdown_spread: f64,
+}
+
+#[derive(Serialize)]
+struct Diversity {
+    generations: Vec<GenerationDiversity>,
+    /// Per gene entropy of the last generation
+    angle_entropy: Vec<f64>,
+    thrust_entropy: Vec<f64>,
+}
+
 #[derive(Clone)]
 struct AppState {
     state: Arc<Mutex<App>>,
+    checkpoint_file_path: PathBuf,
 }
 
 #[tokio::main]
@@ -90,10 +132,14 @@ async fn main() {
         .nth(1)
         .expect("Lacking simulation path argument");
     let settings_file_path = env::args().nth(2).expect("Lacking settings path argument");
+    let checkpoint_file_path = env::args()
+        .nth(3)
+        .unwrap_or(DEFAULT_CHECKPOINT_FILE.to_string());
 
     let app = match App::try_from_files(&sim_file_path, &settings_file_path) {
         Ok(app) => AppState {
             state: Arc::new(Mutex::new(app)),
+            checkpoint_file_path: checkpoint_file_path.into(),
         },
         Err(e) => panic!("{e}"),
     };
@@ -102,7 +148,12 @@ async fn main() {
     let router = Router::new()
         .route("/terrain", get(handle_terrain))
         .route("/population", get(handle_population))
+        .route("/pareto", get(handle_pareto))
+        .route("/diversity", get(handle_diversity))
         .route("/next", put(handle_next))
+        .route("/solve/:budget_ms", put(handle_solve))
+        .route("/checkpoint", put(handle_checkpoint))
+        .route("/restore", put(handle_restore))
         .route(
             "/reset",
             put(|State(state): State<AppState>| async move {
@@ -130,25 +181,56 @@ async fn handle_terrain(State(state): State<AppState>) -> Json<Value> {
     Json(serde_json::to_value(v).unwrap())
 }
 
+/// Logs `error` with its sources and turns it into a response
+fn internal_error(context: &str, error: &dyn std::error::Error) -> (StatusCode, String) {
+    let mut message = format!("{context}: {error}");
+    let mut source = error.source();
+    while let Some(error) = source {
+        message += &format!(": {error}");
+        source = error.source();
+    }
+    eprintln!("{message}");
+    (StatusCode::INTERNAL_SERVER_ERROR, message)
+}
+
 async fn handle_next(State(state): State<AppState>) -> Result<String, (StatusCode, String)> {
     let mut app = state.state.lock().unwrap();
-    let found_solution = app
-        .run()
-        .map_err(|e: String| {
-            let e = format!("App run failed: {e}");
-            eprintln!("{e}");
-            (StatusCode::INTERNAL_SERVER_ERROR, e)
-        })?
-        .is_some();
-    app.next_population().map_err(|e: String| {
-        let e = format!("App next population failed: {e}");
-        eprintln!("{e}");
-        (StatusCode::INTERNAL_SERVER_ERROR, e)
-    })?;
+    let generation = app
+        .generations()
+        .next()
+        .transpose()
+        .map_err(|e| internal_error("App generation failed", &e))?;
+    let found_solution = generation.is_some_and(|generation| generation.landed.is_some());
     Ok(found_solution.to_string())
 }
 
-async fn handle_population(State(AppState { state }): State<AppState>) -> Json<Value> {
+async fn handle_solve(
+    State(state): State<AppState>,
+    Path(budget_ms): Path<u64>,
+) -> Result<String, (StatusCode, String)> {
+    let mut app = state.state.lock().unwrap();
+    let solution = app
+        .solve_for(Duration::from_millis(budget_ms))
+        .map_err(|e| internal_error("App solve failed", &e))?;
+    Ok(solution.landed.to_string())
+}
+
+async fn handle_checkpoint(State(state): State<AppState>) -> Result<(), (StatusCode, String)> {
+    let app = state.state.lock().unwrap();
+    app.save_checkpoint(&state.checkpoint_file_path)
+        .map_err(|e| internal_error("App checkpoint failed", &e))
+}
+
+async fn handle_restore(State(state): State<AppState>) -> Result<(), (StatusCode, String)> {
+    let mut app = state.state.lock().unwrap();
+    *app = App::try_from_checkpoint_file(&state.checkpoint_file_path)
+        .map_err(|e| internal_error("App restore failed", &e))?;
+    app.run()
+        .map_err(|e| internal_error("App run failed", &e))?;
+    Ok(())
+}
+
+async fn handle_population(State(AppState { state, .. }): State<AppState>) -> Json<Value> {
     let app = state.lock().unwrap();
     let routes = app
         .get_routes()
@@ -165,6 +247,47 @@ async fn handle_population(State(AppState { state }): State<AppState>) -> Json<V
     Json(serde_json::to_value(population).unwrap())
 }
 
+async fn handle_pareto(State(AppState { state, .. }): State<AppState>) -> Json<Value> {
+    let app = state.lock().unwrap();
+    let front = app
+        .get_pareto_front()
+        .into_iter()
+        .map(ParetoMember::from)
+        .collect::<Vec<_>>();
+    Json(serde_json::to_value(front).unwrap())
+}
+
+async fn handle_diversity(State(AppState { state, .. }): State<AppState>) -> Json<Value> {
+    let app = state.lock().unwrap();
+    let history = app.get_fitness_history();
+    let generations = history
+        .best()
+        .iter()
+        .zip(history.mean())
+        .zip(app.get_diversity_history())
+        .map(|((best, mean), diversity)| GenerationDiversity {
+            best_fitness: *best,
+            mean_fitness: *mean,
+            gene_distance: diversity.gene_distance,
+            mean_entropy: diversity.mean_entropy(),
+            touchdown_spread: diversity.touchdown_spread,
+        })
+        .collect();
+    let last = app
+        .get_diversity_history()
+        .last()
+        .cloned()
+        .unwrap_or_default();
+    Json(
+        serde_json::to_value(Diversity {
+            generations,
+            angle_entropy: last.angle_entropy,
+            thrust_entropy: last.thrust_entropy,
+        })
+        .unwrap(),
+    )
+}
+
 fn lander_states_to_route(
     (states, flight_state): (impl Iterator<Item = LanderState>, &simulation::FlightState),
 ) -> Route {
@@ -1,95 +1,541 @@
-use crate::simulation::*;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    replay_flight, simulation::*, AppBuilder, ConfigError, Error, Generations, HistoryRecording,
+    Replay, SimulationError, SolverError, TerrainError,
+};
+
+/// Probability of mutating a gene of the autopilot's flight copies seeding the population
+const SEED_PERTURBATION_PROB: f64 = 0.05;
+/// Steps after which the autopilot's flight seeding the population is cut
+const AUTOPILOT_MAX_STEPS: usize = 1000;
+
+/// Callback receiving the report of every generation, see [`App::add_observer`]
+type Observer = Box<dyn FnMut(&GenerationReport) + Send>;
+
+#[derive(Debug, Clone, Default)]
+pub struct SolveStats {
+    pub generations: usize,
+    /// Landers simulated, flights reused whole from the cache aren't counted
+    pub simulations: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct Solution {
+    /// Accumulated (absolute) commands of the best lander
+    pub chromosome: Chromosome,
+    pub landed: bool,
+    /// Best [`FitnessFunction::calculate_comparable`] of the last generation, comparable between
+    /// solutions
+    pub fitness: f64,
+    pub stats: SolveStats,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuelImprovement {
+    pub generation: usize,
+    pub fuel: i32,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuelOptimization {
+    /// Accumulated (absolute) commands of the correct landing with the most fuel left
+    pub chromosome: Chromosome,
+    pub fuel: i32,
+    /// Every increase of the best remaining fuel, in order of occurrence
+    pub improvements: Vec<FuelImprovement>,
+    pub stats: SolveStats,
+}
 
 pub struct App {
+    settings: Settings,
+    physics: PhysicsSettings,
+    landing: LandingCriteria,
+    history: HistoryRecording,
     terrain: Terrain,
     lander_runner: LanderRunner,
     initial_lander_state: LanderState,
     flight_histories: Vec<LanderHistory>,
-    solver: Solver,
+    optimizer: Box<dyn Optimizer>,
+    fitness_function: Box<dyn FitnessFunction>,
+    /// Waypoints to the landing zone, see [`Settings::planner`]
+    corridor: Option<Vec<Vec2>>,
+    local_search: Option<LocalSearchSettings>,
     current_fitness: Vec<f64>,
+    evaluated_population: Vec<Chromosome>,
+    best_chromosome: Option<Chromosome>,
+    fitness_history: FitnessHistory,
+    diversity_history: Vec<Diversity>,
+    simulation_cache: SimulationCache,
+    cache_stats: CacheStats,
     population_id: usize,
+    simulations: usize,
+    /// Time the last [`App::run`] spent simulating
+    simulation_time: Duration,
+    observers: Vec<Observer>,
 }
 
 impl App {
+    /// App with the default physics and landing criteria, see [`AppBuilder`] for others
     pub fn try_new(
         initial_lander_state: LanderState,
         terrain: Terrain,
         settings: Settings,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, Error> {
+        AppBuilder::new(initial_lander_state, terrain)
+            .with_settings(settings)
+            .build()
+    }
+
+    /// Builds the app from options validated by [`AppBuilder::build`]
+    pub(crate) fn try_build(builder: AppBuilder) -> Result<Self, Error> {
+        let AppBuilder {
+            initial_lander_state,
+            terrain,
+            settings,
+            physics,
+            landing,
+            seed,
+            history,
+        } = builder;
         let solver_settings = SolverSettings {
             chromosome_size: settings.chromosome_size,
             elitism: settings.elitism,
             mutation_prob: settings.mutation_prob,
+            unique_elites: settings.unique_elites,
+            encoding: settings.encoding,
             population_size: settings.population_size,
             initial_angle: initial_lander_state.angle as i32,
             initial_thrust: initial_lander_state.power,
+            seed,
         };
-        let solver = Solver::try_new(solver_settings)?;
+        let optimizer = settings.algorithm.create_optimizer(solver_settings)?;
         let lander_runner = LanderRunner::new(
             initial_lander_state.clone(),
             settings.population_size,
-            Physics::default(),
-            CollisionChecker::try_with_default_limits(terrain.clone())
-                .ok_or("Failed to create collision checker")?,
-        );
+            physics.physics(),
+            landing
+                .collision_checker(terrain.clone())
+                .ok_or(TerrainError::NoLandingZone)?,
+        )
+        .with_final_approach(settings.final_approach.clone(), &physics);
+        let fitness_function: Box<dyn FitnessFunction> = if settings.multi_objective {
+            let checker = lander_runner.collision_checker();
+            Box::new(ParetoFitness::new(
+                checker.max_vertical_speed(),
+                checker.max_horizontal_speed(),
+            ))
+        } else {
+            Box::new(DefaultFitness::default())
+        };
+        let corridor = match &settings.planner {
+            Some(planner) => Some(
+                plan_corridor(
+                    &terrain,
+                    Vec2 {
+                        x: initial_lander_state.x,
+                        y: initial_lander_state.y,
+                    },
+                    planner,
+                )
+                .ok_or(TerrainError::NoCorridor)?,
+            ),
+            None => None,
+        };
+        let fitness_function: Box<dyn FitnessFunction> = match &corridor {
+            Some(corridor) => Box::new(CorridorFitness::new(
+                fitness_function,
+                corridor.clone(),
+                terrain.clone(),
+            )),
+            None => fitness_function,
+        };
         let flight_histories: Vec<_> =
             vec![
                 LanderHistory::with_initial_state(initial_lander_state.clone());
                 settings.population_size
             ];
 
-        Ok(Self {
+        let mut app = Self {
+            physics,
+            landing,
+            history,
             terrain,
             lander_runner,
             initial_lander_state,
             flight_histories,
-            solver,
+            optimizer,
+            fitness_function,
+            corridor,
+            local_search: settings.local_search.clone(),
             current_fitness: vec![0f64; settings.population_size],
+            evaluated_population: Vec::new(),
+            best_chromosome: None,
+            fitness_history: FitnessHistory::default(),
+            diversity_history: Vec::new(),
+            simulation_cache: SimulationCache::default(),
+            cache_stats: CacheStats::default(),
             population_id: 0,
-        })
+            simulations: 0,
+            simulation_time: Duration::ZERO,
+            observers: Vec::new(),
+            settings,
+        };
+        if app.settings.heuristic_seed != 0. {
+            app.seed_with_autopilot(app.settings.heuristic_seed, app.settings.chromosome_size)?;
+        }
+        Ok(app)
     }
 
-    pub fn next_population(&mut self) -> Result<(), String> {
-        let fitness = calculate_fitness(
-            &self
-                .lander_runner
-                .current_flight_states()
-                .map(|f| {
-                    if let FlightState::Landed(l) = f {
-                        Ok(l.clone())
-                    } else {
-                        Err(format!("Lander not landed: {f:?}"))
-                    }
+    /// Resumes a run saved with [`App::checkpoint`]
+    ///
+    /// Only the genetic algorithm can be resumed. Local search and stagnation restarts don't draw
+    /// from the solver's random generator, so runs using them won't repeat exactly, and the
+    /// fitness history starts over.
+    pub fn try_from_checkpoint(checkpoint: Checkpoint) -> Result<Self, Error> {
+        let Checkpoint {
+            settings,
+            initial_lander_state,
+            terrain,
+            generation,
+            initial_angle,
+            initial_thrust,
+            population,
+            rng,
+        } = checkpoint;
+        let check = |key, expected: f64, actual: f64| {
+            if expected == actual {
+                Ok(())
+            } else {
+                Err(ConfigError::Mismatch {
+                    key,
+                    expected,
+                    actual,
                 })
-                .collect::<Result<Vec<_>, String>>()?,
-        )
-        .ok_or("Failed to calculate fitness")?;
-        self.solver.new_generation(fitness.iter().copied())?;
+            }
+        };
+        check(
+            "InitialAngle",
+            initial_lander_state.angle as i32 as f64,
+            initial_angle as f64,
+        )?;
+        check(
+            "InitialThrust",
+            initial_lander_state.power as f64,
+            initial_thrust as f64,
+        )?;
+        check(
+            "PopulationSize",
+            settings.population_size as f64,
+            population.len() as f64,
+        )?;
+        for c in &population {
+            for size in [c.angles.len(), c.thrusts.len()] {
+                check(
+                    "ChromosomeSize",
+                    settings.chromosome_size as f64,
+                    size as f64,
+                )?;
+            }
+        }
+
+        // the population is restored, no need to seed it
+        let mut app = Self::try_new(
+            initial_lander_state,
+            terrain,
+            Settings {
+                heuristic_seed: 0.,
+                ..settings.clone()
+            },
+        )?;
+        app.settings = settings;
+        if !app.optimizer.restore_rng(&rng) {
+            return Err(app.checkpoints_unsupported().into());
+        }
+        app.optimizer
+            .population_mut()
+            .iter_mut()
+            .zip(population)
+            .for_each(|(c, restored)| *c = restored);
+        app.population_id = generation;
+        Ok(app)
+    }
+
+    /// Snapshot of the solver state, taken between generations: the current population is the one
+    /// the next [`App::run`] simulates
+    pub fn checkpoint(&self) -> Result<Checkpoint, ConfigError> {
+        if self.physics != PhysicsSettings::default() || self.landing != LandingCriteria::default()
+        {
+            return Err(ConfigError::Requires {
+                setting: "Checkpoint",
+                requirement: "default physics and landing criteria",
+            });
+        }
+        let rng = self
+            .optimizer
+            .rng_state()
+            .ok_or_else(|| self.checkpoints_unsupported())?;
+        Ok(Checkpoint {
+            settings: self.settings.clone(),
+            initial_lander_state: self.initial_lander_state.clone(),
+            terrain: self.terrain.clone(),
+            generation: self.population_id,
+            initial_angle: self.initial_lander_state.angle as i32,
+            initial_thrust: self.initial_lander_state.power,
+            population: self.optimizer.iter_population().cloned().collect(),
+            rng,
+        })
+    }
+
+    pub fn with_fitness_function(self, fitness_function: impl FitnessFunction + 'static) -> Self {
+        Self {
+            fitness_function: Box::new(fitness_function),
+            ..self
+        }
+    }
+
+    /// Calls `observer` with the report of every following generation, see
+    /// [`App::next_population`]
+    pub fn add_observer(&mut self, observer: impl FnMut(&GenerationReport) + Send + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Ranks the landers of the last [`App::run`] and evolves the next population, reporting the
+    /// generation to the observers
+    pub fn next_population(&mut self) -> Result<GenerationReport, Error> {
+        let start = Instant::now();
+        let mut fitness = self.calculate_fitness()?;
+        if let Some(local_search) = self.local_search.clone() {
+            self.refine_best(&fitness, &local_search)?;
+            fitness = self.calculate_fitness()?;
+        }
+        self.best_chromosome = fitness
+            .iter()
+            .zip(&self.evaluated_population)
+            .max_by(|(fitness1, _), (fitness2, _)| fitness1.total_cmp(fitness2))
+            .map(|(_, c)| c.clone());
+        let comparable_fitness = self.comparable_fitness()?;
+        if self.history.fitness {
+            let best_absolute = comparable_fitness
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max);
+            self.fitness_history.record(&fitness, best_absolute);
+        }
+        let diversity = self.history.diversity.then(|| self.measure_diversity());
+        let gene_usage = self.gene_usage();
+        let mut report = self.report(&fitness)?;
+        match (self.settings.stagnation.clone(), &diversity) {
+            // stagnation requires both histories, see `AppBuilder::validate`
+            (Some(stagnation), Some(diversity))
+                if self
+                    .fitness_history
+                    .is_stagnating(&stagnation, diversity.gene_distance) =>
+            {
+                self.restart_population(&fitness, &stagnation)?;
+                self.fitness_history.restarted();
+            }
+            _ if self.optimizer.remembers_fitness() => {
+                self.optimizer.new_generation(&comparable_fitness)?
+            }
+            _ => self.optimizer.new_generation(&fitness)?,
+        }
+        if let Some(adaptive_horizon) = &self.settings.adaptive_horizon {
+            let size =
+                adapted_horizon(adaptive_horizon, self.settings.chromosome_size, &gene_usage);
+            self.resize_population(size);
+        }
+        self.diversity_history.extend(diversity);
+        report.simulation_time = self.simulation_time;
+        report.solver_time = start.elapsed();
+        self.observers
+            .iter_mut()
+            .for_each(|observer| observer(&report));
         self.current_fitness = fitness;
         self.population_id += 1;
-        Ok(())
+        Ok(report)
     }
 
-    pub fn run(&mut self) -> Result<Option<Chromosome>, String> {
+    /// Simulates the current population, returning the commands of a correctly landing lander if
+    /// any
+    ///
+    /// Flights of the last run are cached: landers flying the same commands reuse their history
+    /// and landing, the ones sharing leading commands continue from where they split. Fitness is
+    /// still calculated anew as it's relative to the whole population.
+    pub fn run(&mut self) -> Result<Option<Chromosome>, SimulationError> {
+        let start = Instant::now();
         self.lander_runner
             .reinitialize(self.initial_lander_state.clone());
-        self.flight_histories.iter_mut().for_each(|h| {
-            *h = LanderHistory::with_initial_state(self.initial_lander_state.clone())
-        });
-        let mut population: Vec<_> = self.solver.iter_accumulated_population().collect();
-        let res = loop {
-            if let ExecutionStatus::Finished(maybe_id) = self
-                .lander_runner
-                .iterate(&mut population)
-                .map_err(|e| e.to_string())?
-            {
-                break maybe_id
-                    .map(|correct_landing_id| population.get(correct_landing_id).unwrap());
+        let commands: Vec<_> = self.optimizer.iter_accumulated_population().collect();
+        let mut evaluated_population = Vec::with_capacity(commands.len());
+        let mut cached_flights = Vec::with_capacity(commands.len());
+        for (id, commands) in commands.into_iter().enumerate() {
+            let (evaluated, history, flight_state) = match self.simulation_cache.lookup(&commands) {
+                Some(CacheHit::Full {
+                    evaluated,
+                    history,
+                    flight_state,
+                }) => {
+                    self.cache_stats.full_hits += 1;
+                    self.cache_stats.reused_steps += history.steps();
+                    (evaluated, history, flight_state)
+                }
+                hit => {
+                    let (mut evaluated, history) = match hit {
+                        Some(CacheHit::Prefix { evaluated, history }) => {
+                            self.cache_stats.prefix_hits += 1;
+                            self.cache_stats.reused_steps += history.steps();
+                            (evaluated, history)
+                        }
+                        _ => (
+                            commands.clone(),
+                            LanderHistory::with_initial_state(self.initial_lander_state.clone()),
+                        ),
+                    };
+                    let reused_steps = history.steps();
+                    let (history, landing) = self.lander_runner.simulate_from(
+                        history,
+                        &mut LimitedController::new(&mut evaluated, &self.physics),
+                    )?;
+                    self.cache_stats.simulated_steps += history.steps() - reused_steps;
+                    self.simulations += 1;
+                    (evaluated, history, FlightState::Landed(landing))
+                }
+            };
+            self.lander_runner
+                .set_lander(id, &history, flight_state.clone());
+            cached_flights.push(CachedFlight {
+                commands,
+                evaluated: evaluated.clone(),
+                history: history.clone(),
+                flight_state,
+            });
+            self.flight_histories[id] = history;
+            evaluated_population.push(evaluated);
+        }
+        self.simulation_cache = SimulationCache::new(cached_flights);
+        self.evaluated_population = evaluated_population;
+        self.simulation_time = start.elapsed();
+        Ok(self
+            .lander_runner
+            .current_flight_states()
+            .position(|state| matches!(state, FlightState::Landed(Landing::Correct)))
+            .map(|id| self.evaluated_population[id].clone()))
+    }
+
+    /// Flights found in the cache of the previous run so far, see [`App::run`]
+    pub fn get_cache_stats(&self) -> &CacheStats {
+        &self.cache_stats
+    }
+
+    /// Generations evolved one by one, e.g. `app.generations().take(10)`
+    pub fn generations(&mut self) -> Generations<'_> {
+        Generations::new(self)
+    }
+
+    /// Runs generations until a correct landing is found or the time budget is exhausted.
+    ///
+    /// A new generation is started only if the slowest generation so far still fits in the
+    /// remaining budget, so the call should not overrun it. At least one generation is always run.
+    /// Returns the correctly landing chromosome or, if none was found, the best one from the last
+    /// evaluated generation.
+    pub fn solve_for(&mut self, budget: Duration) -> Result<Solution, Error> {
+        self.solve_until(budget, &AtomicBool::new(false))
+    }
+
+    /// Like [`App::solve_for`], but also stops after the generation during which `cancel` was set
+    pub fn solve_until(
+        &mut self,
+        budget: Duration,
+        cancel: &AtomicBool,
+    ) -> Result<Solution, Error> {
+        let start = Instant::now();
+        let start_simulations = self.simulations;
+        let mut stats = SolveStats::default();
+        let mut slowest_generation = Duration::ZERO;
+        let mut generation_start = Instant::now();
+        let mut landed = None;
+        for generation in self.generations() {
+            stats.generations += 1;
+            if let Some(chromosome) = generation?.landed {
+                landed = Some(chromosome);
+                break;
             }
-            self.save_last_lander_states_in_flight()
-        };
-        self.save_last_lander_states();
-        Ok(res.cloned())
+            slowest_generation = slowest_generation.max(generation_start.elapsed());
+            generation_start = Instant::now();
+            // checked after the generation, so at least one is run
+            if start.elapsed() + slowest_generation > budget || cancel.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        stats.elapsed = start.elapsed();
+        stats.simulations = self.simulations - start_simulations;
+        Ok(Solution {
+            landed: landed.is_some(),
+            fitness: self
+                .comparable_fitness()?
+                .into_iter()
+                .fold(f64::NEG_INFINITY, f64::max),
+            chromosome: match landed {
+                Some(chromosome) => chromosome,
+                None => self
+                    .best_chromosome
+                    .clone()
+                    .ok_or(SolverError::EmptyPopulation)?,
+            },
+            stats,
+        })
+    }
+
+    /// Keeps evolving the population for the whole time budget, also after the first correct
+    /// landing, looking for the correct landing with the most fuel left.
+    ///
+    /// Like [`App::solve_for`] it doesn't start a generation which wouldn't fit in the remaining
+    /// budget, but always runs at least one. Returns `None` if no lander landed correctly.
+    pub fn optimize_fuel_for(
+        &mut self,
+        budget: Duration,
+    ) -> Result<Option<FuelOptimization>, Error> {
+        let start = Instant::now();
+        let start_simulations = self.simulations;
+        let mut stats = SolveStats::default();
+        let mut slowest_generation = Duration::ZERO;
+        let mut best: Option<(Chromosome, i32)> = None;
+        let mut improvements = Vec::new();
+        let mut generation_start = Instant::now();
+        // a new iterator per generation, the last run is inspected between them
+        while let Some(generation) = self.generations().next() {
+            let generation = generation?;
+            stats.generations += 1;
+            if let Some((id, fuel)) = self.best_correct_landing() {
+                if best.as_ref().is_none_or(|(_, best_fuel)| fuel > *best_fuel) {
+                    improvements.push(FuelImprovement {
+                        generation: generation.report.generation,
+                        fuel,
+                        elapsed: start.elapsed(),
+                    });
+                    best = Some((self.evaluated_population[id].clone(), fuel));
+                }
+            }
+            slowest_generation = slowest_generation.max(generation_start.elapsed());
+            generation_start = Instant::now();
+            if start.elapsed() + slowest_generation > budget {
+                break;
+            }
+        }
+        stats.elapsed = start.elapsed();
+        stats.simulations = self.simulations - start_simulations;
+        Ok(best.map(|(chromosome, fuel)| FuelOptimization {
+            chromosome,
+            fuel,
+            improvements,
+            stats,
+        }))
     }
 
     pub fn get_routes(&self) -> impl Iterator<Item = impl Iterator<Item = LanderState> + '_> + '_ {
@@ -104,12 +550,110 @@ impl App {
         self.lander_runner.current_flight_states()
     }
 
+    /// Objectives of every landed lander from the last run, `None` for the ones still flying
+    pub fn get_objectives(&self) -> Vec<Option<Objectives>> {
+        let checker = self.lander_runner.collision_checker();
+        let pareto =
+            ParetoFitness::new(checker.max_vertical_speed(), checker.max_horizontal_speed());
+        self.flight_histories
+            .iter()
+            .zip(self.lander_runner.current_flight_states())
+            .map(|(h, f)| match f {
+                FlightState::Landed(l) => pareto.objectives(h, l),
+                FlightState::Flying => None,
+            })
+            .collect()
+    }
+
+    /// Ids and objectives of the landers from the last run which are not dominated by any other
+    pub fn get_pareto_front(&self) -> Vec<(usize, Objectives)> {
+        let (ids, objectives): (Vec<_>, Vec<_>) = self
+            .get_objectives()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(id, o)| Some((id, o?)))
+            .unzip();
+        non_dominated_sort(&objectives)
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|i| (ids[i], objectives[i].clone()))
+            .collect()
+    }
+
     pub fn get_population(&self) -> impl Iterator<Item = &Chromosome> + '_ {
-        self.solver.iter_population()
+        self.optimizer.iter_population()
     }
 
     pub fn get_population_accumulated(&self) -> impl Iterator<Item = Chromosome> + '_ {
-        self.solver.iter_accumulated_population()
+        self.optimizer.iter_accumulated_population()
+    }
+
+    /// Flies the lander from its initial state with a closed-loop `controller`, e.g. to compare it
+    /// with the evolved plans
+    ///
+    /// Returns absolute commands executed, flight history and landing if it landed within
+    /// `max_steps`.
+    pub fn fly(
+        &self,
+        controller: &mut impl Controller,
+        max_steps: usize,
+    ) -> Result<(Chromosome, LanderHistory, Option<Landing>), SimulationError> {
+        self.lander_runner.fly(
+            self.initial_lander_state.clone(),
+            max_steps,
+            &mut LimitedController::new(controller, &self.physics),
+        )
+    }
+
+    /// Replays absolute `commands` from the initial state with the app's physics and landing
+    /// criteria, see [`replay_flight`]
+    pub fn replay(&self, commands: &[Command], max_steps: usize) -> Result<Replay, Error> {
+        replay_flight(
+            self.initial_lander_state.clone(),
+            self.terrain.clone(),
+            &self.physics,
+            &self.landing,
+            commands,
+            max_steps,
+        )
+    }
+
+    /// Step at which the final approach engaged for each lander of the last run, if it did
+    pub fn get_final_approach_steps(&self) -> Vec<Option<usize>> {
+        self.flight_histories
+            .iter()
+            .map(|h| h.final_approach_step())
+            .collect()
+    }
+
+    /// Best chromosome (accumulated) of the last generation passed to [`App::next_population`]
+    pub fn get_best_chromosome(&self) -> Option<&Chromosome> {
+        self.best_chromosome.as_ref()
+    }
+
+    /// Best and mean fitness of every generation passed to [`App::next_population`], empty unless
+    /// recorded, see [`HistoryRecording`]
+    pub fn get_fitness_history(&self) -> &FitnessHistory {
+        &self.fitness_history
+    }
+
+    /// Diversity of every generation passed to [`App::next_population`], empty unless recorded, see
+    /// [`HistoryRecording`]
+    pub fn get_diversity_history(&self) -> &[Diversity] {
+        &self.diversity_history
+    }
+
+    /// Current chromosome size, adapted to the flights when
+    /// [`Settings::adaptive_horizon`] is set
+    pub fn get_horizon(&self) -> usize {
+        self.settings.chromosome_size
+    }
+
+    /// Waypoints from the lander to the landing zone planned when [`Settings::planner`] is set
+    pub fn get_corridor(&self) -> Option<&[Vec2]> {
+        self.corridor.as_deref()
     }
 
     pub fn get_terrain(&self) -> &Terrain {
@@ -120,26 +664,263 @@ impl App {
         self.population_id
     }
 
-    fn save_last_lander_states_in_flight(&mut self) {
+    fn calculate_fitness(&self) -> Result<Vec<f64>, Error> {
+        Ok(self
+            .fitness_function
+            .calculate(&self.landed_flights()?)
+            .ok_or(SolverError::Fitness)?)
+    }
+
+    /// Fitness of every lander of the last run comparable between generations, see
+    /// [`FitnessFunction::calculate_comparable`]
+    fn comparable_fitness(&self) -> Result<Vec<f64>, Error> {
+        Ok(self
+            .fitness_function
+            .calculate_comparable(&self.landed_flights()?)
+            .ok_or(SolverError::Fitness)?)
+    }
+
+    /// Report of the last run ranked with `fitness`, without the solver time
+    fn report(&self, fitness: &[f64]) -> Result<GenerationReport, SimulationError> {
+        Ok(GenerationReport::measure(
+            self.population_id,
+            fitness,
+            &self.landed_flights()?,
+            &self.landing,
+        ))
+    }
+
+    /// History and landing of every lander of the last run
+    fn landed_flights(&self) -> Result<Vec<(&LanderHistory, &Landing)>, SimulationError> {
         self.flight_histories
-            .iter_mut()
-            .zip(self.lander_runner.current_landers_states())
+            .iter()
             .zip(self.lander_runner.current_flight_states())
-            .filter_map(|(x, s)| {
-                if let FlightState::Flying = s {
-                    Some(x)
+            .enumerate()
+            .map(|(id, (h, f))| {
+                if let FlightState::Landed(l) = f {
+                    Ok((h, l))
                 } else {
-                    None
+                    Err(SimulationError::NotLanded { id })
                 }
             })
-            .for_each(|(h, s)| h.append_lander_state(s))
+            .collect()
     }
 
-    fn save_last_lander_states(&mut self) {
-        self.flight_histories
+    /// Hill-climbs the best landers of the last run, storing improvements in the population
+    fn refine_best(
+        &mut self,
+        fitness: &[f64],
+        settings: &LocalSearchSettings,
+    ) -> Result<(), Error> {
+        let mut ranking: Vec<_> = (0..fitness.len()).collect();
+        ranking.sort_by(|a, b| fitness[*a].total_cmp(&fitness[*b]).reverse());
+        let population: Vec<_> = self.optimizer.iter_population().cloned().collect();
+        let flight_states: Vec<_> = self
+            .lander_runner
+            .current_flight_states()
+            .cloned()
+            .collect();
+        for id in ranking.into_iter().take(settings.top_k) {
+            let FlightState::Landed(landing) = &flight_states[id] else {
+                continue;
+            };
+            let start = Candidate {
+                genes: population[id].clone(),
+                commands: self.evaluated_population[id].clone(),
+                history: self.flight_histories[id].clone(),
+                landing: landing.clone(),
+            };
+            let (refined, simulations) = hill_climb(
+                start,
+                settings,
+                self.fitness_function.as_ref(),
+                self.optimizer.encoding(),
+                |genes| {
+                    let mut commands = self.optimizer.decode(&genes);
+                    let (history, landing) = self.lander_runner.simulate(
+                        self.initial_lander_state.clone(),
+                        &mut LimitedController::new(&mut commands, &self.physics),
+                    )?;
+                    Ok(Candidate {
+                        genes,
+                        commands,
+                        history,
+                        landing,
+                    })
+                },
+            )?;
+            self.simulations += simulations;
+            self.lander_runner.set_lander(
+                id,
+                &refined.history,
+                FlightState::Landed(refined.landing),
+            );
+            self.flight_histories[id] = refined.history;
+            self.evaluated_population[id] = refined.commands;
+            self.optimizer.population_mut()[id] = refined.genes;
+        }
+        Ok(())
+    }
+
+    /// Replaces `fraction` of the population with the autopilot's flight, the first copy intact and
+    /// the others perturbed
+    fn seed_with_autopilot(&mut self, fraction: f64, chromosome_size: usize) -> Result<(), Error> {
+        let genes = self.autopilot_genes(chromosome_size)?;
+        let seeded = (self.optimizer.iter_population().count() as f64 * fraction).round() as usize;
+        let mut rng = rand::thread_rng();
+        let copies: Vec<_> = (0..seeded)
+            .map(|id| {
+                let mut copy = genes.clone();
+                if id > 0 {
+                    self.optimizer
+                        .encoding()
+                        .mutate(&mut copy, SEED_PERTURBATION_PROB, &mut rng);
+                }
+                copy
+            })
+            .collect();
+        self.optimizer
+            .population_mut()
+            .iter_mut()
+            .zip(copies)
+            .for_each(|(chromosome, copy)| *chromosome = copy);
+        Ok(())
+    }
+
+    /// Genes of the autopilot's flight from the initial state, through the corridor if there's one
+    fn autopilot_genes(&self, chromosome_size: usize) -> Result<Chromosome, Error> {
+        if self.terrain.landing_zone().is_none() {
+            return Err(TerrainError::NoLandingZone.into());
+        }
+        let (commands, _, _) = match &self.corridor {
+            Some(corridor) => self.fly(
+                &mut WaypointPilot::new(corridor.clone()),
+                AUTOPILOT_MAX_STEPS,
+            )?,
+            None => self.fly(&mut Autopilot::default(), AUTOPILOT_MAX_STEPS)?,
+        };
+        Ok(self.optimizer.encoding().encode(
+            &commands,
+            self.initial_lander_state.angle as i32,
+            self.initial_lander_state.power,
+            chromosome_size,
+        ))
+    }
+
+    /// Diversity of the population and touchdown points of the last run
+    fn measure_diversity(&self) -> Diversity {
+        let population: Vec<_> = self.optimizer.iter_population().cloned().collect();
+        let touchdowns: Vec<_> = self
+            .flight_histories
+            .iter()
+            .filter_map(|h| Some(h.last_state()?.x))
+            .collect();
+        Diversity::measure(&population, &touchdowns, self.optimizer.encoding())
+    }
+
+    /// Genes used by each lander of the last run
+    fn gene_usage(&self) -> Vec<GeneUsage> {
+        let encoding = self.optimizer.encoding();
+        self.optimizer
+            .iter_population()
+            .zip(&self.evaluated_population)
+            .zip(&self.flight_histories)
+            .map(|((genes, commands), history)| {
+                let turns = history.iter_history().count().saturating_sub(1);
+                GeneUsage {
+                    used: encoding.genes_used(genes, turns),
+                    exhausted: turns > commands.angles.len(),
+                }
+            })
+            .collect()
+    }
+
+    /// Truncates or extends every chromosome of the population to `size` genes
+    fn resize_population(&mut self, size: usize) {
+        if size == self.settings.chromosome_size {
+            return;
+        }
+        let mut population: Vec<_> = self.optimizer.iter_population().cloned().collect();
+        let encoding = self.optimizer.encoding();
+        population
             .iter_mut()
+            .for_each(|genes| encoding.resize(genes, size));
+        self.optimizer
+            .population_mut()
+            .clone_from_slice(&population);
+        self.settings.chromosome_size = size;
+    }
+
+    /// Replaces the population according to the stagnation response, keeping its best members
+    fn restart_population(
+        &mut self,
+        fitness: &[f64],
+        settings: &StagnationSettings,
+    ) -> Result<(), Error> {
+        let population: Vec<_> = self.optimizer.iter_population().cloned().collect();
+        let mut ranking: Vec<_> = (0..fitness.len()).collect();
+        ranking.sort_by(|a, b| fitness[*a].total_cmp(&fitness[*b]).reverse());
+        let n_elites = (settings.elitism * population.len() as f64) as usize;
+        let elites: Vec<_> = ranking[..n_elites]
+            .iter()
+            .map(|id| population[*id].clone())
+            .collect();
+        let chromosome_size = population.first().map_or(0, |c| c.angles.len());
+        let seed = match settings.response {
+            StagnationResponse::Reseed => Some(self.autopilot_genes(chromosome_size)?),
+            _ => None,
+        };
+
+        let encoding = self.optimizer.encoding();
+        let mut rng = rand::thread_rng();
+        let restarted: Vec<_> = population
+            .iter()
+            .enumerate()
+            .map(|(id, chromosome)| {
+                if let Some(elite) = elites.get(id) {
+                    return elite.clone();
+                }
+                match settings.response {
+                    StagnationResponse::CataclysmicMutation => {
+                        let mut mutated = if elites.is_empty() {
+                            chromosome.clone()
+                        } else {
+                            elites[id % elites.len()].clone()
+                        };
+                        encoding.mutate(&mut mutated, settings.mutation_prob, &mut rng);
+                        mutated
+                    }
+                    StagnationResponse::Rerandomize => {
+                        encoding.new_random(chromosome_size, &mut rng)
+                    }
+                    StagnationResponse::Reseed => {
+                        let mut copy = seed.clone().expect("autopilot's flight is flown to reseed");
+                        encoding.mutate(&mut copy, SEED_PERTURBATION_PROB, &mut rng);
+                        copy
+                    }
+                }
+            })
+            .collect();
+        self.optimizer.population_mut().clone_from_slice(&restarted);
+        Ok(())
+    }
+
+    fn checkpoints_unsupported(&self) -> ConfigError {
+        ConfigError::Unsupported {
+            feature: "Checkpoint".to_string(),
+            algorithm: self.settings.algorithm,
+        }
+    }
+
+    /// Id and remaining fuel of the correctly landed lander with the most fuel left
+    fn best_correct_landing(&self) -> Option<(usize, i32)> {
+        self.lander_runner
+            .current_flight_states()
             .zip(self.lander_runner.current_landers_states())
-            .for_each(|(h, s)| h.append_lander_state(s))
+            .enumerate()
+            .filter(|(_, (f, _))| matches!(f, FlightState::Landed(Landing::Correct)))
+            .map(|(id, (_, s))| (id, s.fuel))
+            .max_by_key(|(_, fuel)| *fuel)
     }
 
     pub fn print_flight_state_results(&self) {
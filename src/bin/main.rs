@@ -1,13 +1,19 @@
-use simulation::App;
+use simulation::{init::json, AppBuilder, Error};
 use std::env;
 
-fn main() -> Result<(), String> {
+fn main() -> Result<(), Error> {
     let sim_file_path = env::args()
         .nth(1)
         .expect("Lacking simulation path argument");
     let settings_file_path = env::args().nth(2).expect("Lacking settings path argument");
 
-    let mut app = App::try_from_files(&sim_file_path, &settings_file_path)?;
+    let (initial_lander_state, terrain) = json::parse_sim(&sim_file_path)?;
+    let mut settings = json::parse_settings(&settings_file_path)?;
+    // the bot always lands with the final approach, with the settings' parameters if given
+    settings.final_approach.get_or_insert_with(Default::default);
+    let mut app = AppBuilder::new(initial_lander_state, terrain)
+        .with_settings(settings)
+        .build()?;
 
     let now = std::time::Instant::now();
     let result = app.run();
@@ -0,0 +1,147 @@
+use crate::{simulation::*, App, ConfigError, Error};
+
+/// Histories [`App::next_population`] keeps of every generation
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryRecording {
+    /// Best and mean fitness, see [`App::get_fitness_history`]
+    pub fitness: bool,
+    /// Population diversity, see [`App::get_diversity_history`]
+    pub diversity: bool,
+}
+
+impl Default for HistoryRecording {
+    fn default() -> Self {
+        Self {
+            fitness: true,
+            diversity: true,
+        }
+    }
+}
+
+/// Configures an [`App`] beyond its [`Settings`]: physics, landing criteria, random seed and
+/// recorded histories, validating them together on [`AppBuilder::build`]
+///
+/// The autopilot, planner and final approach are tuned for the default physics, with other
+/// gravity or limits they may seed or override worse flights. The seed makes the genetic
+/// algorithm, particle swarm and simulated annealing repeatable, heuristic seeding, local search
+/// and stagnation restarts still draw from the thread's random generator.
+#[derive(Clone)]
+pub struct AppBuilder {
+    pub(crate) initial_lander_state: LanderState,
+    pub(crate) terrain: Terrain,
+    pub(crate) settings: Settings,
+    pub(crate) physics: PhysicsSettings,
+    pub(crate) landing: LandingCriteria,
+    pub(crate) seed: Option<u64>,
+    pub(crate) history: HistoryRecording,
+}
+
+impl AppBuilder {
+    pub fn new(initial_lander_state: LanderState, terrain: Terrain) -> Self {
+        Self {
+            initial_lander_state,
+            terrain,
+            settings: Settings::default(),
+            physics: PhysicsSettings::default(),
+            landing: LandingCriteria::default(),
+            seed: None,
+            history: HistoryRecording::default(),
+        }
+    }
+
+    pub fn with_settings(self, settings: Settings) -> Self {
+        Self { settings, ..self }
+    }
+
+    pub fn with_gravity(mut self, gravity: f64) -> Self {
+        self.physics.gravity = gravity;
+        self
+    }
+
+    pub fn with_power_max(mut self, power_max: i32) -> Self {
+        self.physics.power_max = power_max;
+        self
+    }
+
+    pub fn with_angle_limit(mut self, angle_limit: f64) -> Self {
+        self.physics.angle_limit = angle_limit;
+        self
+    }
+
+    pub fn with_landing(self, landing: LandingCriteria) -> Self {
+        Self { landing, ..self }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.settings.algorithm = algorithm;
+        self
+    }
+
+    /// Seeds the optimizer's random generator, unsupported by CMA-ES, see [`AppBuilder`]
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    pub fn with_history(self, history: HistoryRecording) -> Self {
+        Self { history, ..self }
+    }
+
+    /// Checks every setting is in its range and the options work together
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.physics.validate()?;
+        self.landing.validate()?;
+        let lander = &self.initial_lander_state;
+        ConfigError::check_range(
+            "InitialThrust",
+            lander.power as f64,
+            0.,
+            self.physics.power_max as f64,
+        )?;
+        ConfigError::check_range(
+            "InitialAngle",
+            lander.angle,
+            -self.physics.angle_limit,
+            self.physics.angle_limit,
+        )?;
+        self.settings.validate()?;
+        let algorithm = self.settings.algorithm;
+        if algorithm != Algorithm::Genetic {
+            let feature = if self.settings.adaptive_horizon.is_some() {
+                Some("AdaptiveHorizon")
+            } else if self.seed.is_some() && algorithm == Algorithm::CmaEs {
+                Some("Seed")
+            } else {
+                None
+            };
+            if let Some(feature) = feature {
+                return Err(ConfigError::Unsupported {
+                    feature: feature.to_string(),
+                    algorithm,
+                });
+            }
+        }
+        if self.settings.stagnation.is_some() {
+            if !self.history.fitness {
+                return Err(ConfigError::Requires {
+                    setting: "Stagnation",
+                    requirement: "fitness history",
+                });
+            }
+            if !self.history.diversity {
+                return Err(ConfigError::Requires {
+                    setting: "Stagnation",
+                    requirement: "diversity history",
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn build(self) -> Result<App, Error> {
+        self.validate()?;
+        App::try_build(self)
+    }
+}
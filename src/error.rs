@@ -0,0 +1,383 @@
+use std::{
+    error,
+    fmt::{self, Display, Formatter},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::simulation::{Algorithm, Command};
+
+/// Any failure of the crate, wrapping the error of the stage which failed
+#[derive(Debug)]
+pub enum Error {
+    Config(ConfigError),
+    Parse(ParseError),
+    Terrain(TerrainError),
+    Simulation(SimulationError),
+    Solver(SolverError),
+}
+
+/// Settings out of range, unknown or not working together
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// `value` of setting `key` outside of range [`min`, `max`]
+    OutOfRange {
+        key: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// Name of an enum setting which isn't one of the `expected` names
+    UnknownVariant {
+        key: &'static str,
+        value: String,
+        expected: &'static [&'static str],
+    },
+    /// Setting `key` doesn't match the value it has to agree with, e.g. in a checkpoint
+    Mismatch {
+        key: &'static str,
+        expected: f64,
+        actual: f64,
+    },
+    /// `feature` can't be used with `algorithm`
+    Unsupported {
+        feature: String,
+        algorithm: Algorithm,
+    },
+    /// `setting` can't be used without `requirement`
+    Requires {
+        setting: &'static str,
+        requirement: &'static str,
+    },
+}
+
+/// Failure to read or write a file, `file` is `None` for json parsed from a string
+#[derive(Debug)]
+pub struct ParseError {
+    pub file: Option<PathBuf>,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    Io(io::Error),
+    Json(json::Error),
+    /// Json path of a missing key, e.g. `/Lander/X`
+    MissingKey(String),
+    /// Json path of a value of a wrong type or format
+    InvalidValue(String),
+    /// Json path of a setting rejected while parsing it
+    InvalidSetting {
+        key: String,
+        source: ConfigError,
+    },
+    UnsupportedVersion {
+        version: u32,
+        expected: u32,
+    },
+}
+
+/// Terrain the lander can't be flown over
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerrainError {
+    /// No flat segment to land on
+    NoLandingZone,
+    /// The planner found no corridor from the lander to the landing zone
+    NoCorridor,
+}
+
+/// Failure while flying a lander
+#[derive(Debug, Clone)]
+pub enum SimulationError {
+    /// Command outside of the physics limits
+    InvalidThrust(Command),
+    /// History without any lander state
+    InconsistentState,
+    /// The controller of lander `id` gave no command at step `sub_id`
+    MissingCommand { id: usize, sub_id: usize },
+    /// Lander `id` is still flying although its landing is needed
+    NotLanded { id: usize },
+    /// The flight didn't end within `steps` steps, e.g. hovering forever
+    FlightTooLong { steps: usize },
+}
+
+/// Failure of the search for a landing
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    /// Crossing the `genes` of two parents failed
+    Crossover {
+        genes: &'static str,
+        source: CrossoverError,
+    },
+    /// The fitness function couldn't rank the landings
+    Fitness,
+    /// Number of fitness values doesn't match the population size
+    FitnessLength {
+        expected: usize,
+        actual: usize,
+    },
+    EmptyPopulation,
+    /// Portfolio member `member` panicked
+    MemberPanicked {
+        member: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrossoverError {
+    SizeMismatch { a: usize, b: usize },
+    CrossPointOutOfRange(f64),
+}
+
+impl ConfigError {
+    /// Rejects setting `key` unless its `value` is in range [`min`, `max`]
+    pub(crate) fn check_range(
+        key: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    ) -> Result<(), Self> {
+        if !(min..=max).contains(&value) {
+            return Err(Self::OutOfRange {
+                key,
+                value,
+                min,
+                max,
+            });
+        }
+        Ok(())
+    }
+
+    /// Rejects setting `key` unless its `value` is in range [0,1]
+    pub(crate) fn check_probability(key: &'static str, value: f64) -> Result<(), Self> {
+        Self::check_range(key, value, 0., 1.)
+    }
+}
+
+impl ParseError {
+    pub fn in_file(file: &Path, kind: ParseErrorKind) -> Self {
+        Self {
+            file: Some(file.to_path_buf()),
+            kind,
+        }
+    }
+}
+
+impl SimulationError {
+    pub(crate) fn with_lander_id(self, id: usize) -> Self {
+        match self {
+            Self::MissingCommand { sub_id, .. } => Self::MissingCommand { id, sub_id },
+            other => other,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(e) => e.fmt(f),
+            Self::Parse(e) => e.fmt(f),
+            Self::Terrain(e) => e.fmt(f),
+            Self::Simulation(e) => e.fmt(f),
+            Self::Solver(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange {
+                key,
+                value,
+                min,
+                max,
+            } => write!(f, "{key} ({value}) out of range [{min},{max}]"),
+            Self::UnknownVariant {
+                key,
+                value,
+                expected,
+            } => write!(
+                f,
+                "Unknown {key} {value:?}, expected one of: {}",
+                expected.join(", ")
+            ),
+            Self::Mismatch {
+                key,
+                expected,
+                actual,
+            } => write!(f, "{key} ({actual}) doesn't match {expected}"),
+            Self::Unsupported { feature, algorithm } => {
+                write!(
+                    f,
+                    "{feature} isn't supported by the {algorithm:?} algorithm"
+                )
+            }
+            Self::Requires {
+                setting,
+                requirement,
+            } => write!(f, "{setting} requires {requirement}"),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        match &self.kind {
+            ParseErrorKind::Io(_) => write!(f, "Failed to access the file"),
+            ParseErrorKind::Json(_) => write!(f, "Invalid json"),
+            ParseErrorKind::MissingKey(key) => write!(f, "Couldn't find {key}"),
+            ParseErrorKind::InvalidValue(key) | ParseErrorKind::InvalidSetting { key, .. } => {
+                write!(f, "Invalid value of {key}")
+            }
+            ParseErrorKind::UnsupportedVersion { version, expected } => {
+                write!(f, "Unsupported version {version}, expected {expected}")
+            }
+        }
+    }
+}
+
+impl Display for TerrainError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoLandingZone => write!(f, "Terrain has no flat landing zone"),
+            Self::NoCorridor => write!(f, "No corridor from the lander to the landing zone found"),
+        }
+    }
+}
+
+impl Display for SimulationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidThrust(cmd) => write!(f, "Invalid thrust command {cmd:?}"),
+            Self::InconsistentState => write!(f, "Flight history has no lander state"),
+            Self::MissingCommand { id, sub_id } => {
+                write!(f, "No command for lander {id} at step {sub_id}")
+            }
+            Self::NotLanded { id } => write!(f, "Lander {id} not landed"),
+            Self::FlightTooLong { steps } => write!(f, "Flight didn't end within {steps} steps"),
+        }
+    }
+}
+
+impl Display for SolverError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Crossover { genes, .. } => write!(f, "Failed to cross {genes}"),
+            Self::Fitness => write!(f, "Failed to calculate fitness"),
+            Self::FitnessLength { expected, actual } => write!(
+                f,
+                "Fitness length ({actual}) doesn't match population size ({expected})"
+            ),
+            Self::EmptyPopulation => write!(f, "Empty population"),
+            Self::MemberPanicked { member } => write!(f, "Portfolio member {member} panicked"),
+        }
+    }
+}
+
+impl Display for CrossoverError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SizeMismatch { a, b } => write!(f, "Parents of different sizes ({a} != {b})"),
+            Self::CrossPointOutOfRange(i) => write!(f, "Cross point ({i}) out of range [0,1]"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    // transparent, the wrapped error is already displayed
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Config(e) => e.source(),
+            Self::Parse(e) => e.source(),
+            Self::Terrain(e) => e.source(),
+            Self::Simulation(e) => e.source(),
+            Self::Solver(e) => e.source(),
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::Io(e) => Some(e),
+            ParseErrorKind::Json(e) => Some(e),
+            ParseErrorKind::InvalidSetting { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl error::Error for TerrainError {}
+
+impl error::Error for SimulationError {}
+
+impl error::Error for SolverError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Crossover { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl error::Error for CrossoverError {}
+
+impl From<ParseErrorKind> for ParseError {
+    fn from(kind: ParseErrorKind) -> Self {
+        Self { file: None, kind }
+    }
+}
+
+macro_rules! impl_from {
+    ($($variant:ident($error:ty)),+) => {
+        $(impl From<$error> for Error {
+            fn from(e: $error) -> Self {
+                Self::$variant(e)
+            }
+        })+
+    };
+}
+
+impl_from!(
+    Config(ConfigError),
+    Parse(ParseError),
+    Terrain(TerrainError),
+    Simulation(SimulationError),
+    Solver(SolverError)
+);
+
+#[cfg(test)]
+mod error_test {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn source_chained() {
+        let error = Error::from(ParseError::in_file(
+            Path::new("settings.json"),
+            ParseErrorKind::InvalidSetting {
+                key: "/Stagnation/Response".to_string(),
+                source: ConfigError::UnknownVariant {
+                    key: "Response",
+                    value: "Restart".to_string(),
+                    expected: &["Reseed"],
+                },
+            },
+        ));
+        assert_eq!(
+            error.to_string(),
+            "settings.json: Invalid value of /Stagnation/Response"
+        );
+        assert_eq!(
+            error.source().map(|e| e.to_string()),
+            Some("Unknown Response \"Restart\", expected one of: Reseed".to_string())
+        );
+    }
+}
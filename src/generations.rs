@@ -0,0 +1,254 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{simulation::*, App, Error};
+
+/// Generation simulated and evolved by [`Generations`]
+#[derive(Debug, Clone)]
+pub struct Generation {
+    pub report: GenerationReport,
+    /// Accumulated (absolute) commands of a correctly landing lander, if any
+    pub landed: Option<Chromosome>,
+    /// Best chromosome (accumulated), with [`Generations::with_best_chromosome`] only
+    pub best_chromosome: Option<Chromosome>,
+}
+
+/// Iterator running [`App::run`] and [`App::next_population`] once per item, see
+/// [`App::generations`]
+///
+/// It doesn't end by itself, stopping conditions are left to the iterator adapters or
+/// [`Generations::with_cancel`]. The first error ends it. With the `stream` feature
+/// [`GenerationStream`] is its async counterpart.
+pub struct Generations<'a> {
+    app: &'a mut App,
+    cancel: Option<&'a AtomicBool>,
+    best_chromosome: bool,
+    failed: bool,
+}
+
+impl<'a> Generations<'a> {
+    pub fn new(app: &'a mut App) -> Self {
+        Self {
+            app,
+            cancel: None,
+            best_chromosome: false,
+            failed: false,
+        }
+    }
+
+    /// Ends before the next generation once `cancel` is set
+    pub fn with_cancel(self, cancel: &'a AtomicBool) -> Self {
+        Self {
+            cancel: Some(cancel),
+            ..self
+        }
+    }
+
+    /// Yields the best chromosome of every generation as well
+    pub fn with_best_chromosome(self) -> Self {
+        Self {
+            best_chromosome: true,
+            ..self
+        }
+    }
+
+    fn generation(&mut self) -> Result<Generation, Error> {
+        let landed = self.app.run()?;
+        let report = self.app.next_population()?;
+        Ok(Generation {
+            report,
+            landed,
+            best_chromosome: self
+                .best_chromosome
+                .then(|| self.app.get_best_chromosome().cloned())
+                .flatten(),
+        })
+    }
+}
+
+impl Iterator for Generations<'_> {
+    type Item = Result<Generation, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            return None;
+        }
+        let generation = self.generation();
+        self.failed = generation.is_err();
+        Some(generation)
+    }
+}
+
+/// Async stream of generations owning its [`App`], with the `stream` feature
+///
+/// Like [`Generations`], but every generation runs on a worker thread which wakes the polling task
+/// once done, so polling never blocks the executor. Panics of the worker are resumed on poll.
+#[cfg(feature = "stream")]
+pub struct GenerationStream {
+    app: Option<App>,
+    worker: Option<stream::Worker>,
+    cancel: Option<std::sync::Arc<AtomicBool>>,
+    best_chromosome: bool,
+    failed: bool,
+}
+
+#[cfg(feature = "stream")]
+impl GenerationStream {
+    pub fn new(app: App) -> Self {
+        Self {
+            app: Some(app),
+            worker: None,
+            cancel: None,
+            best_chromosome: false,
+            failed: false,
+        }
+    }
+
+    /// Ends before the next generation once `cancel` is set
+    pub fn with_cancel(self, cancel: std::sync::Arc<AtomicBool>) -> Self {
+        Self {
+            cancel: Some(cancel),
+            ..self
+        }
+    }
+
+    /// Yields the best chromosome of every generation as well
+    pub fn with_best_chromosome(self) -> Self {
+        Self {
+            best_chromosome: true,
+            ..self
+        }
+    }
+
+    /// The app back, waiting for the generation in progress if any
+    pub fn into_app(mut self) -> App {
+        match self.worker.take() {
+            Some(worker) => worker.join().0,
+            None => self
+                .app
+                .take()
+                .expect("app is kept while no generation runs"),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl futures_core::Stream for GenerationStream {
+    type Item = Result<Generation, Error>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        use std::task::Poll;
+
+        let this = self.get_mut();
+        if let Some(worker) = &this.worker {
+            let Some((app, generation)) = worker.poll(cx.waker()) else {
+                return Poll::Pending;
+            };
+            this.worker = None;
+            this.app = Some(app);
+            this.failed = generation.is_err();
+            return Poll::Ready(Some(generation));
+        }
+        if this.failed
+            || this
+                .cancel
+                .as_ref()
+                .is_some_and(|c| c.load(Ordering::Relaxed))
+        {
+            return Poll::Ready(None);
+        }
+        let app = this
+            .app
+            .take()
+            .expect("app is kept while no generation runs");
+        this.worker = Some(stream::Worker::spawn(app, this.best_chromosome, cx.waker()));
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "stream")]
+mod stream {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::{Arc, Mutex, MutexGuard},
+        task::Waker,
+        thread::{self, JoinHandle},
+    };
+
+    use super::{Generation, Generations};
+    use crate::{App, Error};
+
+    type Outcome = thread::Result<(App, Result<Generation, Error>)>;
+
+    /// Outcome of the generation, or the waker of the task waiting for it
+    struct Shared {
+        outcome: Option<Outcome>,
+        waker: Waker,
+    }
+
+    /// Thread running one generation
+    pub(super) struct Worker {
+        handle: JoinHandle<()>,
+        shared: Arc<Mutex<Shared>>,
+    }
+
+    impl Worker {
+        pub(super) fn spawn(mut app: App, best_chromosome: bool, waker: &Waker) -> Self {
+            let shared = Arc::new(Mutex::new(Shared {
+                outcome: None,
+                waker: waker.clone(),
+            }));
+            let handle = thread::spawn({
+                let shared = shared.clone();
+                move || {
+                    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                        let mut generations = Generations::new(&mut app);
+                        if best_chromosome {
+                            generations = generations.with_best_chromosome();
+                        }
+                        let generation = generations
+                            .next()
+                            .expect("generations without cancel always yield");
+                        (app, generation)
+                    }));
+                    let mut shared = lock(&shared);
+                    shared.outcome = Some(outcome);
+                    shared.waker.wake_by_ref();
+                }
+            });
+            Self { handle, shared }
+        }
+
+        /// Outcome of the generation if it ended, otherwise `waker` is woken once it does
+        pub(super) fn poll(&self, waker: &Waker) -> Option<(App, Result<Generation, Error>)> {
+            let mut shared = lock(&self.shared);
+            match shared.outcome.take() {
+                Some(outcome) => Some(resume_panic(outcome)),
+                None => {
+                    shared.waker.clone_from(waker);
+                    None
+                }
+            }
+        }
+
+        pub(super) fn join(self) -> (App, Result<Generation, Error>) {
+            // panics are caught, the outcome is always stored before the thread ends
+            let _ = self.handle.join();
+            let outcome = lock(&self.shared).outcome.take();
+            resume_panic(outcome.expect("worker stores the outcome before ending"))
+        }
+    }
+
+    /// The lock is only held to store or take the outcome, which can't panic
+    fn lock(shared: &Mutex<Shared>) -> MutexGuard<'_, Shared> {
+        shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn resume_panic(outcome: Outcome) -> (App, Result<Generation, Error>) {
+        outcome.unwrap_or_else(|panic| panic::resume_unwind(panic))
+    }
+}
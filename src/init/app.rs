@@ -1,15 +1,28 @@
 use std::path::Path;
 
 use super::json;
-use crate::App;
+use crate::{App, Error};
 
 impl App {
     pub fn try_from_files<P: AsRef<Path>>(
         sim_file_path: P,
         settings_file_path: P,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, Error> {
         let (initial_lander_state, terrain) = json::parse_sim(sim_file_path)?;
         let settings = json::parse_settings(settings_file_path)?;
         Self::try_new(initial_lander_state, terrain, settings)
     }
+
+    pub fn try_from_checkpoint_file<P: AsRef<Path>>(
+        checkpoint_file_path: P,
+    ) -> Result<Self, Error> {
+        Self::try_from_checkpoint(json::parse_checkpoint(checkpoint_file_path)?)
+    }
+
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, checkpoint_file_path: P) -> Result<(), Error> {
+        Ok(json::save_checkpoint(
+            &self.checkpoint()?,
+            checkpoint_file_path,
+        )?)
+    }
 }
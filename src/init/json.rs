@@ -1,17 +1,27 @@
-use crate::simulation::*;
+use crate::{simulation::*, ConfigError, ParseError, ParseErrorKind};
 use json::{self, JsonValue};
-use std::{fs::File, io::Read, path::Path};
+use std::{fs::File, io::Read, path::Path, str::FromStr};
 
 macro_rules! get_json {
-    ($json:ident,$($key:literal),+, $func:ident) => {
-            get_json!($json$([$key])+, concat!($("/",$key),+), $func)
-    };
-
-    ($value:expr, $key:expr, $func:ident) => {
-        $value
+    ($json:ident,$($key:literal),+, $func:ident) => {{
+        let value = &$json$([$key])+;
+        value
             .$func()
-            .ok_or(concat!("Couldn't find ", $key))?
-    };
+            .ok_or_else(|| value_error(value, concat!($("/",$key),+)))?
+    }};
+}
+
+macro_rules! get_json_or {
+    ($json:ident,$($key:literal),+, $func:ident, $default:expr) => {{
+        let value = &$json$([$key])+;
+        if value.is_null() {
+            $default
+        } else {
+            value
+                .$func()
+                .ok_or_else(|| value_error(value, concat!($("/",$key),+)))?
+        }
+    }};
 }
 
 macro_rules! json_value_or_err {
@@ -19,7 +29,7 @@ macro_rules! json_value_or_err {
             {
                 let value = &$json$([$key])+;
                 if value.is_null() {
-                    Err(concat!("Lacking", concat!($("/",$key),+), " key"))
+                    Err(ParseErrorKind::MissingKey(concat!($("/",$key),+).to_string()))
                 }else{
                     Ok(value)
                 }
@@ -27,67 +37,512 @@ macro_rules! json_value_or_err {
     };
 }
 
-pub fn parse_settings<P: AsRef<Path>>(settings_file_path: P) -> Result<Settings, String> {
-    let settings_json = read_json(settings_file_path)?;
+pub fn parse_settings<P: AsRef<Path>>(settings_file_path: P) -> Result<Settings, ParseError> {
+    let path = settings_file_path.as_ref();
+    read_json(path)
+        .and_then(|settings_json| parse_settings_json(&settings_json))
+        .map_err(|kind| ParseError::in_file(path, kind))
+}
 
+fn parse_settings_json(settings_json: &JsonValue) -> Result<Settings, ParseErrorKind> {
     let settings = Settings {
         population_size: get_json!(settings_json, "PopulationSize", as_usize),
         chromosome_size: get_json!(settings_json, "ChromosomeSize", as_usize),
         elitism: get_json!(settings_json, "Elitism", as_f64),
         mutation_prob: get_json!(settings_json, "MutationProb", as_f64),
+        unique_elites: get_json_or!(settings_json, "UniqueElites", as_bool, false),
+        multi_objective: get_json_or!(settings_json, "MultiObjective", as_bool, false),
+        algorithm: parse_setting(
+            get_json_or!(settings_json, "Algorithm", as_str, "Genetic"),
+            "/Algorithm",
+        )?,
+        encoding: parse_setting(
+            get_json_or!(settings_json, "Encoding", as_str, "Delta"),
+            "/Encoding",
+        )?,
+        local_search: parse_local_search(settings_json)?,
+        heuristic_seed: get_json_or!(settings_json, "HeuristicSeed", as_f64, 0.),
+        final_approach: parse_final_approach(settings_json)?,
+        stagnation: parse_stagnation(settings_json)?,
+        adaptive_horizon: parse_adaptive_horizon(settings_json)?,
+        planner: parse_planner(settings_json)?,
     };
-    Ok(settings)
+    validated(settings, "", Settings::validate)
 }
 
-pub fn parse_sim<P: AsRef<Path>>(sim_file_path: P) -> Result<(LanderState, Terrain), String> {
-    let sim_json = read_json(sim_file_path)?;
+/// Settings in the format read by [`parse_settings`]
+fn settings_to_json(settings: &Settings) -> JsonValue {
+    let mut settings_json = json::object! {
+        "PopulationSize": settings.population_size,
+        "ChromosomeSize": settings.chromosome_size,
+        "Elitism": settings.elitism,
+        "MutationProb": settings.mutation_prob,
+        "UniqueElites": settings.unique_elites,
+        "MultiObjective": settings.multi_objective,
+        "Algorithm": format!("{:?}", settings.algorithm),
+        "Encoding": format!("{:?}", settings.encoding),
+        "HeuristicSeed": settings.heuristic_seed,
+    };
+    if let Some(final_approach) = &settings.final_approach {
+        settings_json["FinalApproach"] = json::object! {
+            "Altitude": final_approach.altitude,
+            "TimeToImpact": final_approach.time_to_impact,
+            "VerticalSpeed": final_approach.vertical_speed,
+        };
+    }
+    if let Some(local_search) = &settings.local_search {
+        settings_json["LocalSearch"] = json::object! {
+            "TopK": local_search.top_k,
+            "MaxSteps": local_search.max_steps,
+        };
+    }
+    if let Some(stagnation) = &settings.stagnation {
+        settings_json["Stagnation"] = json::object! {
+            "Generations": stagnation.generations,
+            "MinDiversity": stagnation.min_diversity,
+            "Response": format!("{:?}", stagnation.response),
+            "Elitism": stagnation.elitism,
+            "MutationProb": stagnation.mutation_prob,
+        };
+    }
+    if let Some(adaptive_horizon) = &settings.adaptive_horizon {
+        settings_json["AdaptiveHorizon"] = json::object! {
+            "GrowThreshold": adaptive_horizon.grow_threshold,
+            "Growth": adaptive_horizon.growth,
+            "ShrinkThreshold": adaptive_horizon.shrink_threshold,
+            "MinSize": adaptive_horizon.min_size,
+            "MaxSize": adaptive_horizon.max_size,
+        };
+    }
+    if let Some(planner) = &settings.planner {
+        settings_json["Planner"] = json::object! {
+            "CellSize": planner.cell_size,
+            "Clearance": planner.clearance,
+        };
+    }
+    settings_json
+}
 
-    Ok((
-        parse_lander(&sim_json)?,
-        parse_terrain(json_value_or_err!(sim_json, "Terrain")?)?,
-    ))
+fn parse_local_search(
+    settings_json: &JsonValue,
+) -> Result<Option<LocalSearchSettings>, ParseErrorKind> {
+    if settings_json["LocalSearch"].is_null() {
+        return Ok(None);
+    }
+    Ok(Some(LocalSearchSettings {
+        top_k: get_json!(settings_json, "LocalSearch", "TopK", as_usize),
+        max_steps: get_json!(settings_json, "LocalSearch", "MaxSteps", as_usize),
+    }))
+}
+
+fn parse_final_approach(
+    settings_json: &JsonValue,
+) -> Result<Option<FinalApproachSettings>, ParseErrorKind> {
+    if settings_json["FinalApproach"].is_null() {
+        return Ok(None);
+    }
+    let defaults = FinalApproachSettings::default();
+    let final_approach = FinalApproachSettings {
+        altitude: get_json_or!(
+            settings_json,
+            "FinalApproach",
+            "Altitude",
+            as_f64,
+            defaults.altitude
+        ),
+        time_to_impact: get_json_or!(
+            settings_json,
+            "FinalApproach",
+            "TimeToImpact",
+            as_f64,
+            defaults.time_to_impact
+        ),
+        vertical_speed: get_json_or!(
+            settings_json,
+            "FinalApproach",
+            "VerticalSpeed",
+            as_f64,
+            defaults.vertical_speed
+        ),
+    };
+    validated(
+        final_approach,
+        "/FinalApproach",
+        FinalApproachSettings::validate,
+    )
+    .map(Some)
 }
 
-pub fn parse_from_string(json: &str) -> Result<(LanderState, Terrain), String> {
-    let sim_json = json::parse(json).map_err(|e| format!("Json error: {e}"))?;
+fn parse_stagnation(
+    settings_json: &JsonValue,
+) -> Result<Option<StagnationSettings>, ParseErrorKind> {
+    if settings_json["Stagnation"].is_null() {
+        return Ok(None);
+    }
+    let defaults = StagnationSettings::default();
+    let stagnation = StagnationSettings {
+        generations: get_json_or!(
+            settings_json,
+            "Stagnation",
+            "Generations",
+            as_usize,
+            defaults.generations
+        ),
+        min_diversity: get_json_or!(
+            settings_json,
+            "Stagnation",
+            "MinDiversity",
+            as_f64,
+            defaults.min_diversity
+        ),
+        response: parse_setting(
+            get_json_or!(
+                settings_json,
+                "Stagnation",
+                "Response",
+                as_str,
+                "CataclysmicMutation"
+            ),
+            "/Stagnation/Response",
+        )?,
+        elitism: get_json_or!(
+            settings_json,
+            "Stagnation",
+            "Elitism",
+            as_f64,
+            defaults.elitism
+        ),
+        mutation_prob: get_json_or!(
+            settings_json,
+            "Stagnation",
+            "MutationProb",
+            as_f64,
+            defaults.mutation_prob
+        ),
+    };
+    validated(stagnation, "/Stagnation", StagnationSettings::validate).map(Some)
+}
+
+fn parse_adaptive_horizon(
+    settings_json: &JsonValue,
+) -> Result<Option<AdaptiveHorizonSettings>, ParseErrorKind> {
+    if settings_json["AdaptiveHorizon"].is_null() {
+        return Ok(None);
+    }
+    let defaults = AdaptiveHorizonSettings::default();
+    let adaptive_horizon = AdaptiveHorizonSettings {
+        grow_threshold: get_json_or!(
+            settings_json,
+            "AdaptiveHorizon",
+            "GrowThreshold",
+            as_f64,
+            defaults.grow_threshold
+        ),
+        growth: get_json_or!(
+            settings_json,
+            "AdaptiveHorizon",
+            "Growth",
+            as_f64,
+            defaults.growth
+        ),
+        shrink_threshold: get_json_or!(
+            settings_json,
+            "AdaptiveHorizon",
+            "ShrinkThreshold",
+            as_f64,
+            defaults.shrink_threshold
+        ),
+        min_size: get_json_or!(
+            settings_json,
+            "AdaptiveHorizon",
+            "MinSize",
+            as_usize,
+            defaults.min_size
+        ),
+        max_size: get_json_or!(
+            settings_json,
+            "AdaptiveHorizon",
+            "MaxSize",
+            as_usize,
+            defaults.max_size
+        ),
+    };
+    validated(
+        adaptive_horizon,
+        "/AdaptiveHorizon",
+        AdaptiveHorizonSettings::validate,
+    )
+    .map(Some)
+}
+
+fn parse_planner(settings_json: &JsonValue) -> Result<Option<PlannerSettings>, ParseErrorKind> {
+    if settings_json["Planner"].is_null() {
+        return Ok(None);
+    }
+    let defaults = PlannerSettings::default();
+    let planner = PlannerSettings {
+        cell_size: get_json_or!(
+            settings_json,
+            "Planner",
+            "CellSize",
+            as_f64,
+            defaults.cell_size
+        ),
+        clearance: get_json_or!(
+            settings_json,
+            "Planner",
+            "Clearance",
+            as_f64,
+            defaults.clearance
+        ),
+    };
+    validated(planner, "/Planner", PlannerSettings::validate).map(Some)
+}
+
+pub fn parse_sim<P: AsRef<Path>>(sim_file_path: P) -> Result<(LanderState, Terrain), ParseError> {
+    let path = sim_file_path.as_ref();
+    read_json(path)
+        .and_then(|sim_json| parse_sim_json(&sim_json))
+        .map_err(|kind| ParseError::in_file(path, kind))
+}
+
+pub fn parse_from_string(json: &str) -> Result<(LanderState, Terrain), ParseError> {
+    let sim_json = json::parse(json).map_err(ParseErrorKind::Json)?;
+    Ok(parse_sim_json(&sim_json)?)
+}
+
+fn parse_sim_json(sim_json: &JsonValue) -> Result<(LanderState, Terrain), ParseErrorKind> {
     Ok((
-        parse_lander(&sim_json)?,
+        parse_lander(sim_json)?,
         parse_terrain(json_value_or_err!(sim_json, "Terrain")?)?,
     ))
 }
 
-fn read_json<P: AsRef<Path>>(file_path: P) -> Result<JsonValue, String> {
-    let mut file_content = String::new();
-    let mut file = File::open(&file_path)
-        .map_err(|e| format!("Error while opening file {:?}: {e}", file_path.as_ref()))?;
+/// Reads network weights saved with [`save_network`]
+pub fn parse_network<P: AsRef<Path>>(network_file_path: P) -> Result<NeuralNetwork, ParseError> {
+    let path = network_file_path.as_ref();
+    read_json(path)
+        .and_then(|network_json| parse_network_json(&network_json))
+        .map_err(|kind| ParseError::in_file(path, kind))
+}
 
-    file.read_to_string(&mut file_content)
-        .map_err(|e| format!("Failed to read file: {e}"))?;
-    json::parse(&file_content).map_err(|e| format!("Json error: {e}"))
+fn parse_network_json(network_json: &JsonValue) -> Result<NeuralNetwork, ParseErrorKind> {
+    let numbers = |key: &str| {
+        let value = &network_json[key];
+        let path = format!("/{key}");
+        if !value.is_array() {
+            return Err(value_error(value, &path));
+        }
+        value
+            .members()
+            .map(|v| v.as_f64())
+            .collect::<Option<Vec<_>>>()
+            .ok_or(ParseErrorKind::InvalidValue(path))
+    };
+    let layers = numbers("Layers")?.into_iter().map(|l| l as usize).collect();
+    let weights = numbers("Weights")?;
+    NeuralNetwork::try_from_weights(layers, weights).map_err(|source| {
+        ParseErrorKind::InvalidSetting {
+            key: "/Weights".to_string(),
+            source,
+        }
+    })
 }
 
-fn parse_terrain(terrain_array: &JsonValue) -> Result<Terrain, String> {
-    terrain_array
+/// Saves network weights as `{"Layers": [...], "Weights": [...]}`
+pub fn save_network<P: AsRef<Path>>(
+    network: &NeuralNetwork,
+    network_file_path: P,
+) -> Result<(), ParseError> {
+    let network_json = json::object! {
+        "Layers": network.layers(),
+        "Weights": network.weights(),
+    };
+    write_json(network_file_path.as_ref(), &network_json)
+}
+
+/// Reads a checkpoint saved with [`save_checkpoint`], rejecting other format versions
+pub fn parse_checkpoint<P: AsRef<Path>>(checkpoint_file_path: P) -> Result<Checkpoint, ParseError> {
+    let path = checkpoint_file_path.as_ref();
+    read_json(path)
+        .and_then(|checkpoint_json| parse_checkpoint_json(&checkpoint_json))
+        .map_err(|kind| ParseError::in_file(path, kind))
+}
+
+fn parse_checkpoint_json(checkpoint_json: &JsonValue) -> Result<Checkpoint, ParseErrorKind> {
+    let version = get_json!(checkpoint_json, "Version", as_u32);
+    if version != CHECKPOINT_VERSION {
+        return Err(ParseErrorKind::UnsupportedVersion {
+            version,
+            expected: CHECKPOINT_VERSION,
+        });
+    }
+    let population = json_value_or_err!(checkpoint_json, "Population")?
         .members()
-        .map(|point_json| {
-            let err_str = "Terrain has to contain numeric landpoints";
-            let x = point_json[0].as_f64().ok_or(err_str)?;
-            let y = point_json[1].as_f64().ok_or(err_str)?;
-            Ok((x, y))
+        .enumerate()
+        .map(|(id, chromosome_json)| {
+            let genes = |key| {
+                parse_genes(&chromosome_json[key])
+                    .ok_or_else(|| ParseErrorKind::InvalidValue(format!("/Population/{id}/{key}")))
+            };
+            Ok(Chromosome {
+                angles: genes("Angles")?,
+                thrusts: genes("Thrusts")?,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let seed = json_value_or_err!(checkpoint_json, "Rng", "Seed")?
+        .members()
+        .map(|b| b.as_u8())
+        .collect::<Option<Vec<_>>>()
+        .and_then(|seed| seed.try_into().ok())
+        .ok_or(ParseErrorKind::InvalidValue("/Rng/Seed".to_string()))?;
+    let word_pos = get_json!(checkpoint_json, "Rng", "WordPos", as_str)
+        .parse()
+        .map_err(|_| ParseErrorKind::InvalidValue("/Rng/WordPos".to_string()))?;
+
+    Ok(Checkpoint {
+        settings: parse_settings_json(json_value_or_err!(checkpoint_json, "Settings")?)
+            .map_err(|kind| nested("/Settings", kind))?,
+        initial_lander_state: parse_lander(checkpoint_json)?,
+        terrain: parse_terrain(json_value_or_err!(checkpoint_json, "Terrain")?)?,
+        generation: get_json!(checkpoint_json, "Generation", as_usize),
+        initial_angle: get_json!(checkpoint_json, "InitialAngle", as_i32),
+        initial_thrust: get_json!(checkpoint_json, "InitialThrust", as_i32),
+        population,
+        rng: RngState {
+            seed,
+            stream: get_json!(checkpoint_json, "Rng", "Stream", as_u64),
+            word_pos,
+        },
+    })
+}
+
+/// Saves a checkpoint, lander and terrain are stored in the simulation file format
+pub fn save_checkpoint<P: AsRef<Path>>(
+    checkpoint: &Checkpoint,
+    checkpoint_file_path: P,
+) -> Result<(), ParseError> {
+    let lander = &checkpoint.initial_lander_state;
+    let checkpoint_json = json::object! {
+        "Version": CHECKPOINT_VERSION,
+        "Generation": checkpoint.generation,
+        "Settings": settings_to_json(&checkpoint.settings),
+        "Lander": {
+            "X": lander.x,
+            "Y": lander.y,
+            "HSpeed": lander.vx,
+            "VSpeed": lander.vy,
+            "Fuel": lander.fuel,
+            "Angle": lander.angle,
+            "Power": lander.power,
+        },
+        "Terrain": checkpoint
+            .terrain
+            .iter_points()
+            .map(|Vec2 { x, y }| vec![x, y])
+            .collect::<Vec<_>>(),
+        "InitialAngle": checkpoint.initial_angle,
+        "InitialThrust": checkpoint.initial_thrust,
+        "Population": checkpoint
+            .population
+            .iter()
+            .map(|c| json::object! { "Angles": c.angles.clone(), "Thrusts": c.thrusts.clone() })
+            .collect::<Vec<_>>(),
+        "Rng": {
+            "Seed": checkpoint.rng.seed.to_vec(),
+            "Stream": checkpoint.rng.stream,
+            // doesn't fit in a json number
+            "WordPos": checkpoint.rng.word_pos.to_string(),
+        },
+    };
+    write_json(checkpoint_file_path.as_ref(), &checkpoint_json)
+}
+
+/// Genes of a chromosome, `None` unless they're an array of integers
+fn parse_genes(genes_json: &JsonValue) -> Option<Vec<i32>> {
+    if !genes_json.is_array() {
+        return None;
+    }
+    genes_json.members().map(|gene| gene.as_i32()).collect()
+}
+
+fn read_json(file_path: &Path) -> Result<JsonValue, ParseErrorKind> {
+    let mut file_content = String::new();
+    File::open(file_path)
+        .and_then(|mut file| file.read_to_string(&mut file_content))
+        .map_err(ParseErrorKind::Io)?;
+    json::parse(&file_content).map_err(ParseErrorKind::Json)
+}
+
+fn write_json(file_path: &Path, json: &JsonValue) -> Result<(), ParseError> {
+    std::fs::write(file_path, json.pretty(4))
+        .map_err(|e| ParseError::in_file(file_path, ParseErrorKind::Io(e)))
+}
+
+/// Error of a `value` at json `path` which couldn't be read
+fn value_error(value: &JsonValue, path: &str) -> ParseErrorKind {
+    if value.is_null() {
+        ParseErrorKind::MissingKey(path.to_string())
+    } else {
+        ParseErrorKind::InvalidValue(path.to_string())
+    }
+}
+
+/// Enum setting at json `path`, e.g. [`Algorithm`]
+fn parse_setting<T: FromStr<Err = ConfigError>>(
+    value: &str,
+    path: &str,
+) -> Result<T, ParseErrorKind> {
+    value
+        .parse()
+        .map_err(|source| ParseErrorKind::InvalidSetting {
+            key: path.to_string(),
+            source,
         })
-        .try_fold(
-            (Vec::new(), Vec::new()),
-            |(mut xs, mut ys), xy: Result<(f64, f64), String>| {
-                let (x, y) = xy?;
-                xs.push(x);
-                ys.push(y);
-                Ok::<(Vec<f64>, Vec<f64>), String>((xs, ys))
-            },
-        )
-        .map(|(x, y)| Terrain::with_default_limits(x, y))
-}
-
-fn parse_lander(json: &JsonValue) -> Result<LanderState, String> {
+}
+
+/// `settings` of the json object at `prefix` if `validate` accepts them
+fn validated<T>(
+    settings: T,
+    prefix: &str,
+    validate: impl Fn(&T) -> Result<(), ConfigError>,
+) -> Result<T, ParseErrorKind> {
+    validate(&settings).map_err(|source| ParseErrorKind::InvalidSetting {
+        key: match &source {
+            ConfigError::OutOfRange { key, .. } => format!("{prefix}/{key}"),
+            _ => prefix.to_string(),
+        },
+        source,
+    })?;
+    Ok(settings)
+}
+
+/// `kind` of an error within the json object at `prefix`
+fn nested(prefix: &str, kind: ParseErrorKind) -> ParseErrorKind {
+    match kind {
+        ParseErrorKind::MissingKey(key) => ParseErrorKind::MissingKey(prefix.to_string() + &key),
+        ParseErrorKind::InvalidValue(key) => {
+            ParseErrorKind::InvalidValue(prefix.to_string() + &key)
+        }
+        ParseErrorKind::InvalidSetting { key, source } => ParseErrorKind::InvalidSetting {
+            key: prefix.to_string() + &key,
+            source,
+        },
+        other => other,
+    }
+}
+
+fn parse_terrain(terrain_array: &JsonValue) -> Result<Terrain, ParseErrorKind> {
+    let (x, y) = terrain_array
+        .members()
+        .map(|point_json| Some((point_json[0].as_f64()?, point_json[1].as_f64()?)))
+        .collect::<Option<(Vec<_>, Vec<_>)>>()
+        .ok_or(ParseErrorKind::InvalidValue("/Terrain".to_string()))?;
+    Ok(Terrain::with_default_limits(x, y))
+}
+
+fn parse_lander(json: &JsonValue) -> Result<LanderState, ParseErrorKind> {
     Ok(LanderState {
         x: get_json!(json, "Lander", "X", as_f64),
         y: get_json!(json, "Lander", "Y", as_f64),
@@ -1,6 +1,16 @@
 mod app;
+mod builder;
+mod error;
+mod generations;
 pub mod init;
+mod portfolio;
+mod replay;
 mod simulation;
 
 pub use app::*;
+pub use builder::*;
+pub use error::*;
+pub use generations::*;
+pub use portfolio::*;
+pub use replay::*;
 pub use simulation::*;
@@ -0,0 +1,110 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{AppBuilder, ConfigError, Error, Solution, SolveStats, SolverError};
+
+#[derive(Debug, Clone)]
+pub struct PortfolioSolution {
+    /// Best solution of all members, a correct landing if any member found one
+    pub solution: Solution,
+    /// Index of the member's builder which found the solution
+    pub member: usize,
+    /// Solving statistics of every member, in order of the builders
+    pub member_stats: Vec<SolveStats>,
+    pub elapsed: Duration,
+}
+
+/// Solves with an independent [`App`](crate::App) per builder on separate threads. Apps
+/// without a seed draw their own, so the same builder may be repeated for independent restarts.
+///
+/// Stops all members as soon as one of them lands correctly, fails or panics, members finish the
+/// generation they are in. Returns that landing or, once the budget runs out, the best solution of
+/// all members, ranked by their [`Solution::fitness`].
+pub fn solve_portfolio(
+    builders: Vec<AppBuilder>,
+    budget: Duration,
+) -> Result<PortfolioSolution, Error> {
+    if builders.is_empty() {
+        return Err(ConfigError::OutOfRange {
+            key: "Portfolio",
+            value: 0.,
+            min: 1.,
+            max: f64::INFINITY,
+        }
+        .into());
+    }
+    let start = Instant::now();
+    let stop = AtomicBool::new(false);
+    let solutions = thread::scope(|scope| {
+        let handles: Vec<_> = builders
+            .into_iter()
+            .map(|builder| {
+                let stop = &stop;
+                scope.spawn(move || {
+                    run_member(stop, || {
+                        builder
+                            .build()
+                            .and_then(|mut app| app.solve_until(budget, stop))
+                    })
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .enumerate()
+            .map(|(member, handle)| {
+                handle
+                    .join()
+                    .map_err(|_| SolverError::MemberPanicked { member })?
+            })
+            .collect::<Result<Vec<_>, Error>>()
+    })?;
+
+    let member = (0..solutions.len())
+        .max_by(|a, b| solutions[*a].fitness.total_cmp(&solutions[*b].fitness))
+        .ok_or(SolverError::EmptyPopulation)?;
+    Ok(PortfolioSolution {
+        solution: solutions[member].clone(),
+        member,
+        member_stats: solutions
+            .into_iter()
+            .map(|solution| solution.stats)
+            .collect(),
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Runs a member, setting `stop` once it lands or fails, panics included, as a failing member
+/// fails the whole portfolio and there's no need to wait for the others
+fn run_member(
+    stop: &AtomicBool,
+    solve: impl FnOnce() -> Result<Solution, Error>,
+) -> Result<Solution, Error> {
+    let solution = panic::catch_unwind(AssertUnwindSafe(solve)).unwrap_or_else(|panic| {
+        stop.store(true, Ordering::Relaxed);
+        panic::resume_unwind(panic)
+    });
+    if !matches!(solution, Ok(Solution { landed: false, .. })) {
+        stop.store(true, Ordering::Relaxed);
+    }
+    solution
+}
+
+#[cfg(test)]
+mod portfolio_test {
+    use super::*;
+
+    #[test]
+    fn panicking_member_stops_others() {
+        let stop = AtomicBool::new(false);
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_member(&stop, || panic!("member failed"))
+        }));
+        assert!(panicked.is_err());
+        assert!(stop.load(Ordering::Relaxed));
+    }
+}
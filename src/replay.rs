@@ -0,0 +1,63 @@
+use crate::{simulation::*, Error, SimulationError, TerrainError};
+
+/// Flight of a command list simulated by [`replay_flight`]
+#[derive(Clone)]
+pub struct Replay {
+    pub history: LanderHistory,
+    /// `None` if the lander was still flying after `max_steps`
+    pub landing: Option<Landing>,
+    /// Touchdown measured against the landing criteria, if it landed
+    pub touchdown: Option<TouchdownMargins>,
+}
+
+/// Flies `commands` from `initial_lander_state` over `terrain`, independently of any solver, e.g.
+/// to verify a saved solution or compare physics
+///
+/// Commands are absolute and the last one is repeated when they run out. Commands outside the
+/// physics limits fail with [`SimulationError::InvalidThrust`], they aren't clamped, so replay
+/// checks solutions. Neither the final approach nor any other controller overrides them, so
+/// solutions should be replayed with the commands the solver evaluated, e.g.
+/// [`Solution::chromosome`](crate::Solution::chromosome).
+pub fn replay_flight(
+    initial_lander_state: LanderState,
+    terrain: Terrain,
+    physics: &PhysicsSettings,
+    landing: &LandingCriteria,
+    commands: &[Command],
+    max_steps: usize,
+) -> Result<Replay, Error> {
+    physics.validate()?;
+    landing.validate()?;
+    let checker = landing
+        .collision_checker(terrain)
+        .ok_or(TerrainError::NoLandingZone)?;
+    let physics = physics.physics();
+    let mut history = LanderHistory::with_initial_state(initial_lander_state.clone());
+    let mut lander = initial_lander_state;
+    for step in 0..max_steps {
+        let Some(cmd) = commands.get(step).or(commands.last()) else {
+            return Err(SimulationError::MissingCommand {
+                id: 0,
+                sub_id: step,
+            }
+            .into());
+        };
+        let new_lander = physics.iterate(lander.clone(), cmd.clone())?;
+        if let Some(((x, y), verdict)) = checker.check(&lander, &new_lander) {
+            let touchdown = LanderState { x, y, ..new_lander };
+            history.append_lander_state(&touchdown);
+            return Ok(Replay {
+                history,
+                landing: Some(verdict),
+                touchdown: Some(TouchdownMargins::measure(&touchdown, landing)),
+            });
+        }
+        history.append_lander_state(&new_lander);
+        lander = new_lander;
+    }
+    Ok(Replay {
+        history,
+        landing: None,
+        touchdown: None,
+    })
+}
@@ -0,0 +1,177 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use super::{
+    solver::{Angle, Thrust, ANGLE_STEP_RANGE, THRUST_STEP_RANGE},
+    Chromosome, DeltaEncoding, Encoding, Optimizer, SolverSettings,
+};
+use crate::{ConfigError, SolverError};
+
+const INITIAL_TEMPERATURE: f64 = 0.1;
+const COOLING_RATE: f64 = 0.95;
+const MIN_TEMPERATURE: f64 = 1e-4;
+
+/// Independent simulated annealing chains, one per population member.
+///
+/// The population holds neighbours (mutated copies) of the chains' current chromosomes. After
+/// evaluation each chain moves to its neighbour if it's better or, with Metropolis probability,
+/// if it's worse. Fitness of the current chromosomes is remembered from their evaluation, so it's
+/// given fitness comparable between generations, see [`Optimizer::remembers_fitness`].
+pub struct SimulatedAnnealing {
+    population: Vec<Chromosome>,
+    current: Vec<Option<(Chromosome, f64)>>,
+    temperature: f64,
+    mutation_prob: f64,
+    initial_angle: Angle,
+    initial_thrust: Thrust,
+    /// Drives the neighbours and acceptance, seeded by [`SolverSettings::seed`]
+    rng: ChaCha12Rng,
+}
+
+impl SimulatedAnnealing {
+    pub fn try_new(settings: SolverSettings) -> Result<Self, ConfigError> {
+        ConfigError::check_probability("MutationProb", settings.mutation_prob)?;
+        let mut rng = settings
+            .seed
+            .map_or_else(ChaCha12Rng::from_entropy, ChaCha12Rng::seed_from_u64);
+        Ok(Self {
+            population: (0..settings.population_size)
+                .map(|_| DeltaEncoding.new_random(settings.chromosome_size, &mut rng))
+                .collect(),
+            current: vec![None; settings.population_size],
+            temperature: INITIAL_TEMPERATURE,
+            mutation_prob: settings.mutation_prob,
+            initial_angle: settings.initial_angle,
+            initial_thrust: settings.initial_thrust,
+            rng,
+        })
+    }
+
+    /// Copy of `chromosome` with each gene pair mutated with `mutation_prob`, always at least one
+    fn neighbour(chromosome: &Chromosome, mutation_prob: f64, rng: &mut ChaCha12Rng) -> Chromosome {
+        let mut neighbour = chromosome.clone();
+        neighbour.mutate_with(mutation_prob, rng);
+        if !neighbour.angles.is_empty() {
+            let id = rng.gen_range(0..neighbour.angles.len());
+            neighbour.angles[id] = rng.gen_range(ANGLE_STEP_RANGE);
+            neighbour.thrusts[id] = rng.gen_range(THRUST_STEP_RANGE);
+        }
+        neighbour
+    }
+}
+
+impl Optimizer for SimulatedAnnealing {
+    fn iter_population(&self) -> Box<dyn Iterator<Item = &Chromosome> + '_> {
+        Box::new(self.population.iter())
+    }
+
+    fn population_mut(&mut self) -> &mut [Chromosome] {
+        &mut self.population
+    }
+
+    fn decode(&self, chromosome: &Chromosome) -> Chromosome {
+        chromosome.accumulated(self.initial_angle, self.initial_thrust)
+    }
+
+    fn new_generation(&mut self, fitness: &[f64]) -> Result<(), SolverError> {
+        if fitness.len() != self.population.len() {
+            return Err(SolverError::FitnessLength {
+                expected: self.population.len(),
+                actual: fitness.len(),
+            });
+        }
+        let rng = &mut self.rng;
+        for ((current, candidate), fitness) in self
+            .current
+            .iter_mut()
+            .zip(self.population.drain(..))
+            .zip(fitness)
+        {
+            let accept = match current {
+                None => true,
+                Some((_, current_fitness)) => {
+                    *fitness >= *current_fitness
+                        || rng.gen_range(0f64..1.)
+                            < ((*fitness - *current_fitness) / self.temperature).exp()
+                }
+            };
+            if accept {
+                *current = Some((candidate, *fitness));
+            }
+        }
+        self.temperature = (self.temperature * COOLING_RATE).max(MIN_TEMPERATURE);
+        self.population = self
+            .current
+            .iter()
+            .flatten()
+            .map(|(c, _)| Self::neighbour(c, self.mutation_prob, &mut self.rng))
+            .collect();
+        Ok(())
+    }
+
+    fn remembers_fitness(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod annealing_test {
+    use super::*;
+    use crate::simulation::EncodingKind;
+
+    fn annealing() -> SimulatedAnnealing {
+        SimulatedAnnealing::try_new(SolverSettings {
+            population_size: 4,
+            chromosome_size: 5,
+            initial_angle: 0,
+            initial_thrust: 0,
+            elitism: 0.,
+            mutation_prob: 0.1,
+            unique_elites: false,
+            encoding: EncodingKind::Delta,
+            seed: None,
+        })
+        .unwrap()
+    }
+
+    fn currents(annealing: &SimulatedAnnealing) -> Vec<Chromosome> {
+        annealing
+            .current
+            .iter()
+            .flatten()
+            .map(|(c, _)| c.clone())
+            .collect()
+    }
+
+    #[test]
+    fn moves_to_better_neighbours_only() {
+        let mut annealing = annealing();
+        let initial = annealing.population.clone();
+        annealing.new_generation(&[0.; 4]).unwrap();
+        assert_eq!(currents(&annealing), initial);
+
+        annealing.new_generation(&[-100.; 4]).unwrap();
+        assert_eq!(currents(&annealing), initial);
+
+        let neighbours = annealing.population.clone();
+        annealing
+            .new_generation(&[1., -100., -100., -100.])
+            .unwrap();
+        let mut expected = initial;
+        expected[0] = neighbours[0].clone();
+        assert_eq!(currents(&annealing), expected);
+        assert_eq!(annealing.population.len(), 4);
+    }
+
+    #[test]
+    fn rejects_fitness_of_wrong_length() {
+        let mut annealing = annealing();
+        assert!(matches!(
+            annealing.new_generation(&[0.; 3]),
+            Err(SolverError::FitnessLength {
+                expected: 4,
+                actual: 3
+            })
+        ));
+    }
+}
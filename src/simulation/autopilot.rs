@@ -0,0 +1,136 @@
+use super::{
+    solver::{clamp, Angle, Thrust},
+    Command, Controller, LanderState, Terrain,
+};
+
+const MAX_POWER: f64 = 4.;
+/// Horizontal deceleration assumed when planning the braking distance
+const BRAKING: f64 = 1.;
+/// Horizontal speed at which descending over the landing zone is considered safe
+const SAFE_HORIZONTAL_SPEED: f64 = 5.;
+/// Steps before touchdown left for levelling the lander
+const LEVELLING_STEPS: f64 = 3.;
+
+#[derive(Clone, Debug)]
+pub struct AutopilotSettings {
+    /// Maximal horizontal speed while flying toward the landing zone
+    pub cruise_speed: f64,
+    /// Height kept above the terrain between the lander and the landing zone
+    pub clearance: f64,
+    /// Vertical speed of the final descent
+    pub descent_speed: f64,
+    /// Maximal tilt in degrees
+    pub max_tilt: f64,
+}
+
+impl Default for AutopilotSettings {
+    fn default() -> Self {
+        Self {
+            cruise_speed: 60.,
+            clearance: 200.,
+            descent_speed: 30.,
+            max_tilt: 30.,
+        }
+    }
+}
+
+/// Rule-based pilot: flies toward the middle of the landing zone keeping clear of the terrain,
+/// cancels horizontal speed over it and then descends
+#[derive(Clone, Debug, Default)]
+pub struct Autopilot {
+    settings: AutopilotSettings,
+}
+
+impl Autopilot {
+    pub fn new(settings: AutopilotSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Absolute command for the current lander state, `None` if the terrain has no landing zone
+    pub fn command(&self, lander: &LanderState, terrain: &Terrain) -> Option<(Angle, Thrust)> {
+        let (left, right) = terrain.landing_zone()?;
+        let target_x = (left.x + right.x) / 2.;
+        let dx = target_x - lander.x;
+        let over_zone = dx.abs() < (right.x - left.x) * 0.4;
+
+        let desired_vx = if over_zone {
+            0.
+        } else {
+            dx.signum()
+                * self
+                    .settings
+                    .cruise_speed
+                    .min((2. * BRAKING * dx.abs()).sqrt())
+        };
+        let descending = over_zone && lander.vx.abs() <= SAFE_HORIZONTAL_SPEED;
+        let desired_vy = if descending {
+            -self.settings.descent_speed
+        } else {
+            let safe_y = terrain.max_height_between(lander.x, target_x) + self.settings.clearance;
+            ((safe_y - lander.y) / 10.).clamp(-self.settings.descent_speed, 20.)
+        };
+
+        let altitude = lander.y - left.y;
+        let angle = if over_zone && altitude < LEVELLING_STEPS * lander.vy.abs().max(10.) {
+            0.
+        } else {
+            // Tilt less while the lander is falling too fast
+            let max_tilt = if desired_vy - lander.vy > 10. {
+                self.settings.max_tilt / 2.
+            } else {
+                self.settings.max_tilt
+            };
+            let ax = ((desired_vx - lander.vx) / 2.).clamp(-MAX_POWER, MAX_POWER);
+            (-(ax / MAX_POWER).asin().to_degrees()).clamp(-max_tilt, max_tilt)
+        };
+        let thrust = if lander.vy < desired_vy {
+            4
+        } else if lander.vy > desired_vy + 5. {
+            2
+        } else {
+            3
+        };
+        Some((clamp(angle.round() as i32, -90..=90), thrust))
+    }
+}
+
+impl Controller for Autopilot {
+    fn command(&mut self, _: usize, lander: &LanderState, terrain: &Terrain) -> Option<Command> {
+        let (angle, thrust) = Autopilot::command(self, lander, terrain)?;
+        Some(Command::new(angle as f64, thrust))
+    }
+}
+
+#[cfg(test)]
+mod autopilot_test {
+    use super::*;
+    use crate::simulation::Physics;
+
+    #[test]
+    fn lands_on_flat_terrain() {
+        let terrain = Terrain::with_default_limits(
+            vec![0., 2000., 3000., 6999.],
+            vec![100., 100., 300., 500.],
+        );
+        let autopilot = Autopilot::default();
+        let physics = Physics::default();
+        let mut lander = LanderState::default()
+            .with_x(5000.)
+            .with_y(2500.)
+            .with_vx(-30.)
+            .with_fuel(2000);
+        for _ in 0..300 {
+            if lander.y <= 100. {
+                break;
+            }
+            let (angle, thrust) = autopilot.command(&lander, &terrain).unwrap();
+            lander = physics
+                .iterate(lander, Command::new(angle as f64, thrust))
+                .unwrap();
+        }
+        assert!(lander.y <= 100.);
+        assert!((200. ..=1800.).contains(&lander.x));
+        assert!(lander.vx.abs() <= 20. && lander.vy.abs() <= 40.);
+        assert_eq!(lander.angle, 0.);
+    }
+}
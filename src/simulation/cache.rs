@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+
+use super::{Chromosome, FlightState, LanderHistory};
+
+/// Flight of absolute `commands` simulated in an earlier run
+#[derive(Clone)]
+pub struct CachedFlight {
+    pub commands: Chromosome,
+    /// Commands after the final approach overrode them
+    pub evaluated: Chromosome,
+    pub history: LanderHistory,
+    pub flight_state: FlightState,
+}
+
+/// Part of a cached flight a new command sequence can reuse
+pub enum CacheHit {
+    /// The flight is the same
+    Full {
+        evaluated: Chromosome,
+        history: LanderHistory,
+        flight_state: FlightState,
+    },
+    /// The first steps of the flight are the same, the rest has to be simulated
+    Prefix {
+        evaluated: Chromosome,
+        history: LanderHistory,
+    },
+}
+
+/// Number of flights found in the cache and of lander steps they saved or still needed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub full_hits: usize,
+    pub prefix_hits: usize,
+    pub reused_steps: usize,
+    pub simulated_steps: usize,
+}
+
+/// Flights of the last run, sorted by their commands to find the longest shared prefix quickly
+#[derive(Default)]
+pub struct SimulationCache {
+    flights: Vec<CachedFlight>,
+}
+
+impl SimulationCache {
+    pub fn new(mut flights: Vec<CachedFlight>) -> Self {
+        flights.sort_by(|a, b| compare_commands(&a.commands, &b.commands));
+        Self { flights }
+    }
+
+    /// Reusable part of the cached flight sharing the longest prefix with `commands`, `None` if
+    /// no flight shares its first command
+    pub fn lookup(&self, commands: &Chromosome) -> Option<CacheHit> {
+        let position = self
+            .flights
+            .binary_search_by(|flight| compare_commands(&flight.commands, commands))
+            .unwrap_or_else(|position| position);
+        let (flight, shared) = [position.checked_sub(1), Some(position)]
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.flights.get(id))
+            .map(|flight| (flight, shared_prefix(&flight.commands, commands)))
+            .max_by_key(|(_, shared)| *shared)?;
+        let flown = flight.history.steps();
+        if flight.commands == *commands || shared >= flown {
+            // commands after the landing don't matter
+            let mut evaluated = commands.clone();
+            copy_prefix(&flight.evaluated, &mut evaluated, flown);
+            Some(CacheHit::Full {
+                evaluated,
+                history: flight.history.clone(),
+                flight_state: flight.flight_state.clone(),
+            })
+        } else if shared > 0 {
+            let mut evaluated = commands.clone();
+            copy_prefix(&flight.evaluated, &mut evaluated, shared);
+            let mut history = flight.history.clone();
+            history.truncate(shared);
+            Some(CacheHit::Prefix { evaluated, history })
+        } else {
+            None
+        }
+    }
+}
+
+/// Lexicographic order of the (angle, thrust) commands
+fn compare_commands(a: &Chromosome, b: &Chromosome) -> Ordering {
+    a.angles
+        .iter()
+        .zip(&a.thrusts)
+        .cmp(b.angles.iter().zip(&b.thrusts))
+}
+
+/// Number of leading commands `a` and `b` have in common
+fn shared_prefix(a: &Chromosome, b: &Chromosome) -> usize {
+    a.angles
+        .iter()
+        .zip(&a.thrusts)
+        .zip(b.angles.iter().zip(&b.thrusts))
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Overrides of the final approach within the first `steps` commands
+fn copy_prefix(from: &Chromosome, to: &mut Chromosome, steps: usize) {
+    let steps = steps.min(from.angles.len()).min(to.angles.len());
+    to.angles[..steps].copy_from_slice(&from.angles[..steps]);
+    to.thrusts[..steps].copy_from_slice(&from.thrusts[..steps]);
+}
+
+#[cfg(test)]
+mod cache_test {
+    use super::*;
+    use crate::simulation::{LanderState, Landing};
+
+    fn commands(angles: &[i32]) -> Chromosome {
+        Chromosome {
+            angles: angles.to_vec(),
+            thrusts: vec![4; angles.len()],
+        }
+    }
+
+    fn flight(angles: &[i32], steps: usize) -> CachedFlight {
+        let mut history = LanderHistory::with_initial_state(LanderState::default());
+        for step in 1..=steps {
+            history.append_lander_state(&LanderState::default().with_x(step as f64));
+        }
+        CachedFlight {
+            commands: commands(angles),
+            evaluated: commands(angles),
+            history,
+            flight_state: FlightState::Landed(Landing::Correct),
+        }
+    }
+
+    #[test]
+    fn longest_prefix_reused() {
+        let cache = SimulationCache::new(vec![
+            flight(&[0, 15, 30, 45], 4),
+            flight(&[0, 15, -15, 0], 4),
+            flight(&[15, 0, 0, 0], 2),
+        ]);
+        let Some(CacheHit::Prefix { history, .. }) = cache.lookup(&commands(&[0, 15, 30, 0]))
+        else {
+            panic!("expected a prefix hit");
+        };
+        assert_eq!(history.iter_history().count(), 4);
+        // landed before the commands differ
+        assert!(matches!(
+            cache.lookup(&commands(&[15, 0, 15, 15])),
+            Some(CacheHit::Full { .. })
+        ));
+        assert!(matches!(
+            cache.lookup(&commands(&[0, 15, -15, 0])),
+            Some(CacheHit::Full { .. })
+        ));
+        assert!(cache.lookup(&commands(&[-15, 0, 0, 0])).is_none());
+    }
+}
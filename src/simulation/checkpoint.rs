@@ -0,0 +1,65 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+use super::{Chromosome, LanderState, Settings, Terrain};
+
+/// Version of the checkpoint format, bumped on every incompatible change
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+/// Position in the random sequence of a solver
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RngState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+    pub word_pos: u128,
+}
+
+impl RngState {
+    pub(super) fn of(rng: &ChaCha12Rng) -> Self {
+        Self {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    }
+
+    pub(super) fn restore(&self) -> ChaCha12Rng {
+        let mut rng = ChaCha12Rng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(self.word_pos);
+        rng
+    }
+}
+
+/// Solver state between generations, resuming from it continues the run exactly where it was
+/// saved
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub settings: Settings,
+    pub initial_lander_state: LanderState,
+    pub terrain: Terrain,
+    /// Number of generations evolved so far
+    pub generation: usize,
+    pub initial_angle: i32,
+    pub initial_thrust: i32,
+    /// Delta encoded genes of the population waiting to be simulated
+    pub population: Vec<Chromosome>,
+    pub rng: RngState,
+}
+
+#[cfg(test)]
+mod checkpoint_test {
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn restored_rng_continues_sequence() {
+        let mut rng = ChaCha12Rng::seed_from_u64(7);
+        rng.gen::<u64>();
+        let mut restored = RngState::of(&rng).restore();
+        let expected: Vec<u32> = (0..10).map(|_| rng.gen()).collect();
+        let actual: Vec<u32> = (0..10).map(|_| restored.gen()).collect();
+        assert_eq!(expected, actual);
+    }
+}
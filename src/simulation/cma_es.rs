@@ -0,0 +1,381 @@
+use super::{
+    optimizer::{from_relaxed, relaxed, standard_normal},
+    solver::{Angle, Thrust},
+    Chromosome, Optimizer, SolverSettings,
+};
+use crate::{ConfigError, SolverError};
+
+const INITIAL_SIGMA: f64 = 0.5;
+
+/// Standard normal sample `z` and its transformation `y = L z` (with `C = L L^T`)
+type Sample = (Vec<f64>, Vec<f64>);
+
+/// Covariance matrix adaptation evolution strategy over the continuous relaxation of chromosome
+/// genes (see `optimizer::from_relaxed`). Sampled genes are rounded to get the evaluated population.
+pub struct CmaEs {
+    population: Vec<Chromosome>,
+    samples: Vec<Sample>,
+    mean: Vec<f64>,
+    sigma: f64,
+    /// Covariance matrix (lower triangle) and its Cholesky factor, both row-major `dim x dim`
+    covariance: Vec<f64>,
+    cholesky: Vec<f64>,
+    sigma_path: Vec<f64>,
+    covariance_path: Vec<f64>,
+    weights: Vec<f64>,
+    params: Params,
+    generation: usize,
+    initial_angle: Angle,
+    initial_thrust: Thrust,
+}
+
+struct Params {
+    mu_eff: f64,
+    c_sigma: f64,
+    d_sigma: f64,
+    c_c: f64,
+    c_1: f64,
+    c_mu: f64,
+    chi_n: f64,
+}
+
+impl Params {
+    fn new(dim: usize, mu_eff: f64) -> Self {
+        let n = dim as f64;
+        let c_sigma = (mu_eff + 2.) / (n + mu_eff + 5.);
+        let c_1 = 2. / ((n + 1.3).powi(2) + mu_eff);
+        Self {
+            mu_eff,
+            c_sigma,
+            d_sigma: 1. + 2. * (((mu_eff - 1.) / (n + 1.)).sqrt() - 1.).max(0.) + c_sigma,
+            c_c: (4. + mu_eff / n) / (n + 4. + 2. * mu_eff / n),
+            c_1,
+            c_mu: (1. - c_1).min(2. * (mu_eff - 2. + 1. / mu_eff) / ((n + 2.).powi(2) + mu_eff)),
+            chi_n: n.sqrt() * (1. - 1. / (4. * n) + 1. / (21. * n * n)),
+        }
+    }
+}
+
+impl CmaEs {
+    pub fn try_new(settings: SolverSettings) -> Result<Self, ConfigError> {
+        if settings.population_size < 2 {
+            return Err(ConfigError::OutOfRange {
+                key: "PopulationSize",
+                value: settings.population_size as f64,
+                min: 2.,
+                max: f64::INFINITY,
+            });
+        }
+        if settings.chromosome_size == 0 {
+            return Err(ConfigError::OutOfRange {
+                key: "ChromosomeSize",
+                value: 0.,
+                min: 1.,
+                max: f64::INFINITY,
+            });
+        }
+        let dim = 2 * settings.chromosome_size;
+        let mu = settings.population_size / 2;
+        let weights: Vec<_> = (1..=mu)
+            .map(|i| (mu as f64 + 0.5).ln() - (i as f64).ln())
+            .collect();
+        let weights_sum: f64 = weights.iter().sum();
+        let weights: Vec<_> = weights.iter().map(|w| w / weights_sum).collect();
+        let mu_eff = 1. / weights.iter().map(|w| w * w).sum::<f64>();
+
+        let mut cma_es = Self {
+            population: Vec::new(),
+            samples: Vec::new(),
+            mean: vec![0.; dim],
+            sigma: INITIAL_SIGMA,
+            covariance: identity(dim),
+            cholesky: identity(dim),
+            sigma_path: vec![0.; dim],
+            covariance_path: vec![0.; dim],
+            weights,
+            params: Params::new(dim, mu_eff),
+            generation: 0,
+            initial_angle: settings.initial_angle,
+            initial_thrust: settings.initial_thrust,
+        };
+        cma_es.sample(settings.population_size);
+        Ok(cma_es)
+    }
+
+    fn dim(&self) -> usize {
+        self.mean.len()
+    }
+
+    fn sample(&mut self, population_size: usize) {
+        let dim = self.dim();
+        let mut rng = rand::thread_rng();
+        self.samples = (0..population_size)
+            .map(|_| {
+                let z: Vec<_> = (0..dim).map(|_| standard_normal(&mut rng)).collect();
+                let y = lower_triangular_mul(&self.cholesky, &z);
+                (z, y)
+            })
+            .collect();
+        self.population = self
+            .samples
+            .iter()
+            .map(|(_, y)| {
+                let x: Vec<_> = self
+                    .mean
+                    .iter()
+                    .zip(y)
+                    .map(|(m, y)| m + self.sigma * y)
+                    .collect();
+                from_relaxed(&x)
+            })
+            .collect();
+    }
+
+    /// Recalculates samples of chromosomes which were replaced in the population
+    fn sync_samples(&mut self) {
+        for (id, chromosome) in self.population.iter().enumerate() {
+            let (_, y) = &self.samples[id];
+            let x: Vec<_> = self
+                .mean
+                .iter()
+                .zip(y)
+                .map(|(m, y)| m + self.sigma * y)
+                .collect();
+            if from_relaxed(&x) != *chromosome {
+                let y: Vec<_> = relaxed(chromosome)
+                    .iter()
+                    .zip(&self.mean)
+                    .map(|(x, m)| (x - m) / self.sigma)
+                    .collect();
+                let z = lower_triangular_solve(&self.cholesky, &y);
+                self.samples[id] = (z, y);
+            }
+        }
+    }
+
+    fn update(&mut self, fitness: &[f64]) -> Result<(), SolverError> {
+        if fitness.len() != self.samples.len() {
+            return Err(SolverError::FitnessLength {
+                expected: self.samples.len(),
+                actual: fitness.len(),
+            });
+        }
+        self.sync_samples();
+        let dim = self.dim();
+        let Params {
+            mu_eff,
+            c_sigma,
+            d_sigma,
+            c_c,
+            c_1,
+            c_mu,
+            chi_n,
+        } = self.params;
+
+        let mut ranking: Vec<_> = (0..fitness.len()).collect();
+        ranking.sort_by(|a, b| fitness[*a].total_cmp(&fitness[*b]).reverse());
+        let selected: Vec<_> = ranking[..self.weights.len()]
+            .iter()
+            .map(|i| &self.samples[*i])
+            .collect();
+
+        let weighted_sum = |pick: fn(&Sample) -> &Vec<f64>| {
+            selected
+                .iter()
+                .zip(&self.weights)
+                .fold(vec![0.; dim], |mut sum, (sample, w)| {
+                    sum.iter_mut()
+                        .zip(pick(sample))
+                        .for_each(|(s, v)| *s += w * v);
+                    sum
+                })
+        };
+        let z_w = weighted_sum(|(z, _)| z);
+        let y_w = weighted_sum(|(_, y)| y);
+
+        self.mean
+            .iter_mut()
+            .zip(&y_w)
+            .for_each(|(m, y)| *m += self.sigma * y);
+
+        let sigma_path_coef = (c_sigma * (2. - c_sigma) * mu_eff).sqrt();
+        self.sigma_path
+            .iter_mut()
+            .zip(&z_w)
+            .for_each(|(p, z)| *p = (1. - c_sigma) * *p + sigma_path_coef * z);
+        let sigma_path_norm = norm(&self.sigma_path);
+
+        self.generation += 1;
+        let h_sigma = sigma_path_norm
+            / (1. - (1. - c_sigma).powi(2 * self.generation as i32)).sqrt()
+            < (1.4 + 2. / (dim as f64 + 1.)) * chi_n;
+        let h_sigma = if h_sigma { 1. } else { 0. };
+
+        let covariance_path_coef = h_sigma * (c_c * (2. - c_c) * mu_eff).sqrt();
+        self.covariance_path
+            .iter_mut()
+            .zip(&y_w)
+            .for_each(|(p, y)| *p = (1. - c_c) * *p + covariance_path_coef * y);
+
+        // update the lower triangle only, it's the part used by Cholesky decomposition
+        let old_weight = 1. - c_1 - c_mu + c_1 * (1. - h_sigma) * c_c * (2. - c_c);
+        for i in 0..dim {
+            let row = &mut self.covariance[i * dim..i * dim + i + 1];
+            let path_i = c_1 * self.covariance_path[i];
+            row.iter_mut()
+                .zip(&self.covariance_path)
+                .for_each(|(c, p)| *c = old_weight * *c + path_i * p);
+        }
+        for ((_, y), w) in selected.iter().zip(&self.weights) {
+            for i in 0..dim {
+                let row = &mut self.covariance[i * dim..i * dim + i + 1];
+                let y_i = c_mu * w * y[i];
+                row.iter_mut().zip(y).for_each(|(c, y_j)| *c += y_i * y_j);
+            }
+        }
+
+        self.sigma *= ((c_sigma / d_sigma) * (sigma_path_norm / chi_n - 1.)).exp();
+
+        self.cholesky = cholesky(&self.covariance, dim).unwrap_or_else(|| {
+            // numerical breakdown, restart the shape of the distribution
+            self.covariance = identity(dim);
+            identity(dim)
+        });
+        Ok(())
+    }
+}
+
+impl Optimizer for CmaEs {
+    fn iter_population(&self) -> Box<dyn Iterator<Item = &Chromosome> + '_> {
+        Box::new(self.population.iter())
+    }
+
+    fn population_mut(&mut self) -> &mut [Chromosome] {
+        &mut self.population
+    }
+
+    fn decode(&self, chromosome: &Chromosome) -> Chromosome {
+        chromosome.accumulated(self.initial_angle, self.initial_thrust)
+    }
+
+    fn new_generation(&mut self, fitness: &[f64]) -> Result<(), SolverError> {
+        self.update(fitness)?;
+        self.sample(self.population.len());
+        Ok(())
+    }
+}
+
+fn identity(dim: usize) -> Vec<f64> {
+    let mut m = vec![0.; dim * dim];
+    (0..dim).for_each(|i| m[i * dim + i] = 1.);
+    m
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+fn lower_triangular_mul(l: &[f64], v: &[f64]) -> Vec<f64> {
+    let dim = v.len();
+    (0..dim)
+        .map(|i| {
+            l[i * dim..i * dim + i + 1]
+                .iter()
+                .zip(v)
+                .map(|(a, b)| a * b)
+                .sum()
+        })
+        .collect()
+}
+
+/// Solves `L x = v` for lower triangular `L`
+fn lower_triangular_solve(l: &[f64], v: &[f64]) -> Vec<f64> {
+    let dim = v.len();
+    let mut x = vec![0.; dim];
+    for i in 0..dim {
+        let sum: f64 = l[i * dim..i * dim + i]
+            .iter()
+            .zip(&x)
+            .map(|(a, b)| a * b)
+            .sum();
+        x[i] = (v[i] - sum) / l[i * dim + i];
+    }
+    x
+}
+
+/// Lower triangular `L` such that `m = L L^T`, `None` if `m` isn't positive definite.
+/// Only the lower triangle of `m` is read.
+fn cholesky(m: &[f64], dim: usize) -> Option<Vec<f64>> {
+    let mut l = vec![0.; dim * dim];
+    for i in 0..dim {
+        for j in 0..=i {
+            let sum: f64 = l[i * dim..i * dim + j]
+                .iter()
+                .zip(&l[j * dim..j * dim + j])
+                .map(|(a, b)| a * b)
+                .sum();
+            if i == j {
+                let d = m[i * dim + i] - sum;
+                if d <= 0. || !d.is_finite() {
+                    return None;
+                }
+                l[i * dim + j] = d.sqrt();
+            } else {
+                l[i * dim + j] = (m[i * dim + j] - sum) / l[j * dim + j];
+            }
+        }
+    }
+    Some(l)
+}
+
+#[cfg(test)]
+mod cma_es_test {
+    use super::*;
+    use crate::simulation::EncodingKind;
+
+    #[test]
+    fn cholesky_decomposition() {
+        let m = [4., 2., 2., 3.];
+        let l = cholesky(&m, 2).unwrap();
+        assert_eq!(l, vec![2., 0., 1., 2f64.sqrt()]);
+        assert!(cholesky(&[1., 2., 2., 1.], 2).is_none());
+    }
+
+    #[test]
+    fn triangular_solve() {
+        let l = [2., 0., 1., 4.];
+        let v = [2., 9.];
+        assert_eq!(lower_triangular_mul(&l, &lower_triangular_solve(&l, &v)), v);
+    }
+
+    #[test]
+    fn converges_to_target() {
+        let mut cma_es = CmaEs::try_new(SolverSettings {
+            population_size: 20,
+            chromosome_size: 5,
+            initial_angle: 0,
+            initial_thrust: 0,
+            elitism: 0.,
+            mutation_prob: 0.,
+            unique_elites: false,
+            encoding: EncodingKind::Delta,
+            seed: None,
+        })
+        .unwrap();
+        let target = 0.3;
+        for _ in 0..100 {
+            let fitness: Vec<_> = cma_es
+                .samples
+                .iter()
+                .map(|(_, y)| {
+                    -y.iter()
+                        .zip(&cma_es.mean)
+                        .map(|(y, m)| (m + cma_es.sigma * y - target).powi(2))
+                        .sum::<f64>()
+                })
+                .collect();
+            cma_es.new_generation(&fitness).unwrap();
+        }
+        assert!(cma_es.mean.iter().all(|m| (m - target).abs() < 0.05));
+    }
+}
@@ -1,11 +1,13 @@
+use crate::ConfigError;
+
 use super::LanderState;
 
-mod defaults {
+pub(super) mod defaults {
     pub const MAX_X: f64 = 7000.;
     pub const MAX_Y: f64 = 3000.;
     pub const MAX_VERTICAL_SPEED: f64 = 40.;
     pub const MAX_HORIZONTAL_SPEED: f64 = 20.;
-    pub const ANGLE_STEP: f64 = crate::simulation::defaults::ANGLE_STEP;
+    pub const ANGLE_STEP: f64 = crate::simulation::physics::defaults::ANGLE_STEP;
 }
 
 #[derive(Clone)]
@@ -40,9 +42,65 @@ impl Terrain {
         Self::new(defaults::MAX_X, defaults::MAX_Y, x, y)
     }
 
+    pub fn max_x(&self) -> f64 {
+        self.max_x
+    }
+
     pub fn max_y(&self) -> f64 {
         self.max_y
     }
+
+    /// First flat terrain segment, ordered by x
+    pub fn landing_zone(&self) -> Option<(Vec2, Vec2)> {
+        self.iter_points()
+            .zip(self.iter_points().skip(1))
+            .find(|(a, b)| a.y == b.y)
+            .map(|(a, b)| if a.x <= b.x { (a, b) } else { (b, a) })
+    }
+
+    /// Height of the terrain right below point (`x`, `y`), e.g. floor of a cave rather than its
+    /// ceiling
+    pub fn ground_below(&self, x: f64, y: f64) -> Option<f64> {
+        self.iter_points()
+            .zip(self.iter_points().skip(1))
+            .filter(|(a, b)| a.x.min(b.x) <= x && x <= a.x.max(b.x))
+            .map(|(a, b)| {
+                if a.x == b.x {
+                    a.y.min(b.y)
+                } else {
+                    a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x)
+                }
+            })
+            .filter(|ground| *ground <= y)
+            .max_by(f64::total_cmp)
+    }
+
+    /// Highest terrain point within `x1..=x2` (in any order), including terrain crossing the ends
+    pub fn max_height_between(&self, x1: f64, x2: f64) -> f64 {
+        let (x1, x2) = (x1.min(x2), x1.max(x2));
+        self.iter_points()
+            .zip(self.iter_points().skip(1))
+            .filter_map(|(a, b)| {
+                let (a, b) = if a.x <= b.x { (a, b) } else { (b, a) };
+                if b.x < x1 || a.x > x2 {
+                    return None;
+                }
+                let height_at = |x: f64| {
+                    if a.x == b.x {
+                        a.y.max(b.y)
+                    } else {
+                        a.y + (b.y - a.y) * (x.clamp(a.x, b.x) - a.x) / (b.x - a.x)
+                    }
+                };
+                Some(
+                    height_at(x1)
+                        .max(height_at(x2))
+                        .max(if a.x >= x1 { a.y } else { f64::MIN })
+                        .max(if b.x <= x2 { b.y } else { f64::MIN }),
+                )
+            })
+            .fold(0., f64::max)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -103,6 +161,44 @@ impl Iterator for MapIterator<'_> {
     }
 }
 
+/// Highest touchdown speeds of a correct landing, see [`AppBuilder`](crate::AppBuilder)
+#[derive(Clone, Debug, PartialEq)]
+pub struct LandingCriteria {
+    pub max_vertical_speed: f64,
+    pub max_horizontal_speed: f64,
+}
+
+impl Default for LandingCriteria {
+    fn default() -> Self {
+        Self {
+            max_vertical_speed: defaults::MAX_VERTICAL_SPEED,
+            max_horizontal_speed: defaults::MAX_HORIZONTAL_SPEED,
+        }
+    }
+}
+
+impl LandingCriteria {
+    /// Checker of landings on `terrain`, `None` if it has no landing zone
+    pub fn collision_checker(&self, terrain: Terrain) -> Option<CollisionChecker> {
+        CollisionChecker::try_new(
+            self.max_vertical_speed,
+            self.max_horizontal_speed,
+            defaults::ANGLE_STEP,
+            terrain,
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (key, value) in [
+            ("MaxVerticalSpeed", self.max_vertical_speed),
+            ("MaxHorizontalSpeed", self.max_horizontal_speed),
+        ] {
+            ConfigError::check_range(key, value, f64::MIN_POSITIVE, f64::INFINITY)?;
+        }
+        Ok(())
+    }
+}
+
 pub struct CollisionChecker {
     max_vertical_speed: f64,
     max_horizontal_speed: f64,
@@ -113,12 +209,7 @@ pub struct CollisionChecker {
 
 impl CollisionChecker {
     pub fn try_with_default_limits(terrain: Terrain) -> Option<Self> {
-        Self::try_new(
-            defaults::MAX_VERTICAL_SPEED,
-            defaults::MAX_HORIZONTAL_SPEED,
-            defaults::ANGLE_STEP,
-            terrain,
-        )
+        LandingCriteria::default().collision_checker(terrain)
     }
 
     fn target_from_terrain(terrain: &Terrain) -> Option<(f64, f64)> {
@@ -196,6 +287,18 @@ impl CollisionChecker {
         None
     }
 
+    pub fn terrain(&self) -> &Terrain {
+        &self.terrain
+    }
+
+    pub fn max_vertical_speed(&self) -> f64 {
+        self.max_vertical_speed
+    }
+
+    pub fn max_horizontal_speed(&self) -> f64 {
+        self.max_horizontal_speed
+    }
+
     pub fn with_max_vertical_speed(self, max_vertical_speed: f64) -> Self {
         assert!(max_vertical_speed > 0.);
         Self {
@@ -254,7 +357,7 @@ impl Vec2 {
     }
 }
 
-fn check_collision(segment_a: (Vec2, Vec2), segment_b: (Vec2, Vec2)) -> Option<Vec2> {
+pub(super) fn check_collision(segment_a: (Vec2, Vec2), segment_b: (Vec2, Vec2)) -> Option<Vec2> {
     // https://stackoverflow.com/questions/563198/how-do-you-detect-where-two-line-segments-intersect
 
     let (p, p1) = segment_a;
@@ -319,6 +422,34 @@ mod collision_checker_tests {
             .with_max_horizontal_speed(20.)
     }
 
+    #[test]
+    fn landing_zone() {
+        assert_eq!(
+            terrain().landing_zone(),
+            Some((Vec2::new(300., 100.), Vec2::new(6700., 100.)))
+        );
+    }
+
+    #[test]
+    fn max_height_between() {
+        assert_eq!(terrain().max_height_between(0., 7000.), 500.);
+        assert_eq!(terrain().max_height_between(1000., 2000.), 100.);
+        assert_eq!(terrain().max_height_between(150., 1000.), 300.);
+        assert_eq!(terrain().max_height_between(6850., 6000.), 300.);
+    }
+
+    #[test]
+    fn ground_below() {
+        let cave = Terrain::with_default_limits(
+            vec![0., 1000., 2000., 1000., 0., 3000.],
+            vec![100., 100., 500., 800., 1000., 1000.],
+        );
+        assert_eq!(cave.ground_below(500., 2000.), Some(1000.));
+        assert_eq!(cave.ground_below(500., 850.), Some(100.));
+        assert_eq!(cave.ground_below(1500., 400.), Some(300.));
+        assert_eq!(cave.ground_below(500., 50.), None);
+    }
+
     #[test]
     fn still_in_flight() {
         let previous_state = LanderState::default().with_x(1000.).with_y(500.);
@@ -0,0 +1,174 @@
+use super::{Chromosome, Command, LanderState, Physics, PhysicsSettings, Terrain};
+
+/// Pilot choosing lander commands step by step, e.g. replaying a fixed plan or reacting to the
+/// current lander state
+pub trait Controller {
+    /// Absolute command executed at `step` by `lander` flying over `terrain`, `None` if the
+    /// controller can't pilot anymore
+    fn command(&mut self, step: usize, lander: &LanderState, terrain: &Terrain) -> Option<Command>;
+
+    /// Called when `cmd` was executed instead of the command of `step`, e.g. by the runner's
+    /// final approach or by [`LimitedController`]
+    fn overridden(&mut self, _step: usize, _cmd: &Command) {}
+}
+
+/// Pilot clamping the commands of `controller` into the angle and power limits of `physics`,
+/// for pilots producing commands within the default limits (the solvers, the autopilot) flown
+/// with tighter ones. Clamped commands are reported to `controller` as overridden.
+///
+/// The runner itself rejects commands outside the limits with
+/// [`SimulationError::InvalidThrust`](crate::SimulationError::InvalidThrust).
+pub struct LimitedController<'a, C: ?Sized> {
+    controller: &'a mut C,
+    physics: Physics,
+}
+
+impl<'a, C: Controller + ?Sized> LimitedController<'a, C> {
+    pub fn new(controller: &'a mut C, physics: &PhysicsSettings) -> Self {
+        Self {
+            controller,
+            physics: physics.physics(),
+        }
+    }
+}
+
+impl<C: Controller + ?Sized> Controller for LimitedController<'_, C> {
+    fn command(&mut self, step: usize, lander: &LanderState, terrain: &Terrain) -> Option<Command> {
+        let cmd = self.controller.command(step, lander, terrain)?;
+        let limited = self.physics.limited(cmd.clone());
+        if (limited.angle(), limited.power()) != (cmd.angle(), cmd.power()) {
+            self.controller.overridden(step, &limited);
+        }
+        Some(limited)
+    }
+
+    fn overridden(&mut self, step: usize, cmd: &Command) {
+        self.controller.overridden(step, cmd)
+    }
+}
+
+/// Open-loop replay of absolute commands, the last one is repeated when they run out.
+/// Commands overridden by the final approach are overwritten.
+impl Controller for Chromosome {
+    fn command(&mut self, step: usize, _: &LanderState, _: &Terrain) -> Option<Command> {
+        let (angle, thrust) = get_id_or_last(self, step)?;
+        Some(Command::new(*angle as f64, *thrust))
+    }
+
+    fn overridden(&mut self, step: usize, cmd: &Command) {
+        if let Some((angle, thrust)) = get_id_or_last(self, step) {
+            *angle = cmd.angle().round() as i32;
+            *thrust = cmd.power();
+        }
+    }
+}
+
+impl<C: Controller + ?Sized> Controller for Box<C> {
+    fn command(&mut self, step: usize, lander: &LanderState, terrain: &Terrain) -> Option<Command> {
+        (**self).command(step, lander, terrain)
+    }
+
+    fn overridden(&mut self, step: usize, cmd: &Command) {
+        (**self).overridden(step, cmd)
+    }
+}
+
+fn get_id_or_last(chromosome: &mut Chromosome, index: usize) -> Option<(&mut i32, &mut i32)> {
+    if index < chromosome.angles.len() {
+        Some((
+            chromosome.angles.get_mut(index)?,
+            chromosome.thrusts.get_mut(index)?,
+        )) // Safe because we checked bounds
+    } else {
+        Some((
+            chromosome.angles.last_mut()?,
+            chromosome.thrusts.last_mut()?,
+        )) // Fallback to last_mut
+    }
+}
+
+#[cfg(test)]
+mod controller_test {
+    use super::*;
+    use crate::simulation::{
+        Autopilot, CollisionChecker, ExecutionStatus, FlightState, LanderRunner, Landing, Physics,
+    };
+
+    #[test]
+    fn runner_drives_mixed_controllers() {
+        let terrain = Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]);
+        let lander = LanderState::default()
+            .with_x(500.)
+            .with_y(1000.)
+            .with_fuel(1000);
+        let mut runner = LanderRunner::new(
+            lander.clone(),
+            2,
+            Physics::default(),
+            CollisionChecker::try_with_default_limits(terrain).unwrap(),
+        );
+        let mut controllers: Vec<Box<dyn Controller>> = vec![
+            Box::new(Chromosome {
+                angles: vec![0],
+                thrusts: vec![0],
+            }),
+            Box::new(Autopilot::default()),
+        ];
+        while let ExecutionStatus::InProgress = runner.iterate(&mut controllers).unwrap() {}
+        assert!(matches!(
+            runner.current_flight_states().collect::<Vec<_>>()[..],
+            [
+                FlightState::Landed(Landing::TooFastVertical { .. }),
+                FlightState::Landed(Landing::Correct)
+            ]
+        ));
+    }
+
+    #[test]
+    fn overridden_chromosome_command() {
+        let mut chromosome = Chromosome {
+            angles: vec![10, 20],
+            thrusts: vec![4, 4],
+        };
+        chromosome.overridden(5, &Command::new(0., 3));
+        assert_eq!(chromosome.angles, vec![10, 0]);
+        assert_eq!(chromosome.thrusts, vec![4, 3]);
+    }
+
+    #[test]
+    fn limited_chromosome_records_clamped_commands() {
+        let terrain = Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]);
+        let settings = PhysicsSettings {
+            power_max: 3,
+            angle_limit: 45.,
+            ..Default::default()
+        };
+        let runner = LanderRunner::new(
+            LanderState::default(),
+            1,
+            settings.physics(),
+            CollisionChecker::try_with_default_limits(terrain).unwrap(),
+        );
+        let lander = LanderState::default()
+            .with_x(500.)
+            .with_y(1000.)
+            .with_fuel(1000);
+        let mut chromosome = Chromosome {
+            angles: vec![-60, 0],
+            thrusts: vec![4, 2],
+        };
+        assert!(matches!(
+            runner.simulate(lander.clone(), &mut chromosome.clone()),
+            Err(crate::SimulationError::InvalidThrust(_))
+        ));
+
+        runner
+            .simulate(
+                lander,
+                &mut LimitedController::new(&mut chromosome, &settings),
+            )
+            .unwrap();
+        assert_eq!(chromosome.angles, vec![-45, 0]);
+        assert_eq!(chromosome.thrusts, vec![3, 2]);
+    }
+}
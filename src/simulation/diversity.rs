@@ -0,0 +1,156 @@
+use std::ops::RangeInclusive;
+
+use super::{solver::clamp, Chromosome, Encoding};
+
+/// Genotype and phenotype diversity of a population
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diversity {
+    /// See [`mean_gene_distance`]
+    pub gene_distance: f64,
+    /// Shannon entropy of each angle gene across the population, normalized to [0,1]
+    pub angle_entropy: Vec<f64>,
+    /// Shannon entropy of each thrust gene across the population, normalized to [0,1]
+    pub thrust_entropy: Vec<f64>,
+    /// Standard deviation of the touchdown x coordinates
+    pub touchdown_spread: f64,
+}
+
+impl Diversity {
+    /// Measures diversity of `population` whose landers touched down at `touchdowns` x coordinates
+    pub fn measure(population: &[Chromosome], touchdowns: &[f64], encoding: &dyn Encoding) -> Self {
+        let (angle_range, thrust_range) = encoding.gene_ranges();
+        Self {
+            gene_distance: mean_gene_distance(population, encoding),
+            angle_entropy: genes_entropy(population, |c| &c.angles, angle_range),
+            thrust_entropy: genes_entropy(population, |c| &c.thrusts, thrust_range),
+            touchdown_spread: std_dev(touchdowns),
+        }
+    }
+
+    /// Entropy averaged over all genes
+    pub fn mean_entropy(&self) -> f64 {
+        let n_genes = self.angle_entropy.len() + self.thrust_entropy.len();
+        if n_genes == 0 {
+            return 0.;
+        }
+        (self.angle_entropy.iter().sum::<f64>() + self.thrust_entropy.iter().sum::<f64>())
+            / n_genes as f64
+    }
+}
+
+/// Mean distance between genes of every pair of chromosomes, each gene scaled by the width of its
+/// range in `encoding`, so 0 means identical chromosomes and 1 the most distant ones possible
+pub fn mean_gene_distance(population: &[Chromosome], encoding: &dyn Encoding) -> f64 {
+    let n = population.len();
+    let n_genes = population
+        .first()
+        .map_or(0, |c| c.angles.len() + c.thrusts.len());
+    if n < 2 || n_genes == 0 {
+        return 0.;
+    }
+    let (angle_range, thrust_range) = encoding.gene_ranges();
+    let total = genes_distance(population, |c| &c.angles, angle_range)
+        + genes_distance(population, |c| &c.thrusts, thrust_range);
+    let pairs = n * (n - 1) / 2;
+    total / (pairs * n_genes) as f64
+}
+
+/// Sum of scaled distances over every pair of chromosomes and every gene
+fn genes_distance(
+    population: &[Chromosome],
+    genes: impl Fn(&Chromosome) -> &[i32],
+    range: RangeInclusive<i32>,
+) -> f64 {
+    let width = (range.end() - range.start()) as f64;
+    let size = population.first().map_or(0, |c| genes(c).len());
+    let mut column = Vec::with_capacity(population.len());
+    (0..size)
+        .map(|gene| {
+            column.clear();
+            column.extend(population.iter().map(|c| genes(c)[gene]));
+            column.sort_unstable();
+            // each sorted value is greater than the ones before it and smaller than the ones after
+            let n = column.len() as i64;
+            column
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (2 * i as i64 - n + 1) * *v as i64)
+                .sum::<i64>() as f64
+                / width
+        })
+        .sum()
+}
+
+fn genes_entropy(
+    population: &[Chromosome],
+    genes: impl Fn(&Chromosome) -> &[i32],
+    range: RangeInclusive<i32>,
+) -> Vec<f64> {
+    let n_values = (range.end() - range.start() + 1) as usize;
+    let size = population.first().map_or(0, |c| genes(c).len());
+    let mut counts = vec![0usize; n_values];
+    (0..size)
+        .map(|gene| {
+            counts.fill(0);
+            for c in population {
+                counts[(clamp(genes(c)[gene], range.clone()) - range.start()) as usize] += 1;
+            }
+            let n = population.len() as f64;
+            let entropy: f64 = counts
+                .iter()
+                .filter(|count| **count > 0)
+                .map(|count| {
+                    let p = *count as f64 / n;
+                    -p * p.ln()
+                })
+                .sum();
+            entropy / (n_values as f64).ln()
+        })
+        .collect()
+}
+
+pub(super) fn std_dev(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt()
+}
+
+#[cfg(test)]
+mod diversity_test {
+    use super::*;
+    use crate::simulation::DeltaEncoding;
+
+    fn chromosomes() -> (Chromosome, Chromosome) {
+        (
+            Chromosome {
+                angles: vec![-15, 0],
+                thrusts: vec![-1, 0],
+            },
+            Chromosome {
+                angles: vec![15, 0],
+                thrusts: vec![1, 0],
+            },
+        )
+    }
+
+    #[test]
+    fn gene_distance() {
+        let (a, b) = chromosomes();
+        let distance = |population: &[Chromosome]| mean_gene_distance(population, &DeltaEncoding);
+        assert_eq!(distance(&[a.clone(), a.clone()]), 0.);
+        assert_eq!(distance(&[a.clone(), b.clone()]), 0.5);
+        assert_eq!(distance(&[a.clone(), a, b]), 1. / 3.);
+    }
+
+    #[test]
+    fn entropy_and_spread() {
+        let (a, b) = chromosomes();
+        let diversity = Diversity::measure(&[a, b], &[100., 300.], &DeltaEncoding);
+        assert!((diversity.thrust_entropy[0] - 2f64.ln() / 3f64.ln()).abs() < 1e-12);
+        assert_eq!(diversity.thrust_entropy[1], 0.);
+        assert_eq!(diversity.touchdown_spread, 100.);
+    }
+}
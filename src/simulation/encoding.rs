@@ -0,0 +1,604 @@
+use std::{ops::RangeInclusive, str::FromStr};
+
+use rand::{Rng, RngCore};
+
+use crate::{ConfigError, CrossoverError, SolverError};
+
+use super::{
+    solver::{
+        clamp, crossed, Angle, Thrust, ANGLE_RANGE, ANGLE_STEP_RANGE, THRUST_RANGE,
+        THRUST_STEP_RANGE,
+    },
+    Chromosome,
+};
+
+/// Maximal number of turns a [`HoldEncoding`] gene holds its command
+const MAX_HOLD: i32 = 10;
+/// Number of thrust levels, packed together with the hold duration in [`HoldEncoding`] genes
+const THRUST_LEVELS: i32 = *THRUST_RANGE.end() + 1;
+/// Number of turns between two [`SplineEncoding`] control points
+const SPLINE_STEP: usize = 5;
+
+/// Interpretation of chromosome genes as lander commands, together with genetic operators
+/// matching it
+pub trait Encoding: Send {
+    fn new_random(&self, size: usize, rng: &mut dyn RngCore) -> Chromosome;
+
+    /// Absolute commands, one per turn, flown from the initial angle and thrust
+    fn decode(
+        &self,
+        genes: &Chromosome,
+        initial_angle: Angle,
+        initial_thrust: Thrust,
+    ) -> Chromosome;
+
+    /// `size` genes flying absolute `commands` as closely as the encoding allows, inverse of
+    /// [`Encoding::decode`]
+    fn encode(
+        &self,
+        commands: &Chromosome,
+        initial_angle: Angle,
+        initial_thrust: Thrust,
+        size: usize,
+    ) -> Chromosome;
+
+    /// Two children mixing `a` and `b`, `cross_point` in range [0,1]
+    fn crossover(
+        &self,
+        a: &Chromosome,
+        b: &Chromosome,
+        cross_point: f64,
+    ) -> Result<(Chromosome, Chromosome), SolverError>;
+
+    /// Randomly changes gene `id`
+    fn mutate_gene(&self, genes: &mut Chromosome, id: usize, rng: &mut dyn RngCore);
+
+    /// Randomly changes each gene with `mutation_prob`
+    fn mutate(&self, genes: &mut Chromosome, mutation_prob: f64, rng: &mut dyn RngCore) {
+        for id in 0..genes.angles.len().min(genes.thrusts.len()) {
+            if rng.gen_range(0f64..1f64) < mutation_prob {
+                self.mutate_gene(genes, id, rng);
+            }
+        }
+    }
+
+    /// Ranges of angle and thrust gene values
+    fn gene_ranges(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>);
+
+    /// Number of genes flying the first `turns` turns
+    fn genes_used(&self, genes: &Chromosome, turns: usize) -> usize {
+        turns.min(genes.angles.len())
+    }
+
+    /// Truncates or extends `genes` to `size`, new genes keep flying the last command
+    fn resize(&self, genes: &mut Chromosome, size: usize) {
+        let last = (
+            genes.angles.last().copied().unwrap_or_default(),
+            genes.thrusts.last().copied().unwrap_or_default(),
+        );
+        genes.angles.resize(size, last.0);
+        genes.thrusts.resize(size, last.1);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EncodingKind {
+    #[default]
+    Delta,
+    Absolute,
+    Hold,
+    Spline,
+}
+
+impl FromStr for EncodingKind {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Delta" => Ok(Self::Delta),
+            "Absolute" => Ok(Self::Absolute),
+            "Hold" => Ok(Self::Hold),
+            "Spline" => Ok(Self::Spline),
+            _ => Err(ConfigError::UnknownVariant {
+                key: "Encoding",
+                value: s.to_string(),
+                expected: &["Delta", "Absolute", "Hold", "Spline"],
+            }),
+        }
+    }
+}
+
+impl EncodingKind {
+    pub fn create_encoding(self) -> Box<dyn Encoding> {
+        match self {
+            Self::Delta => Box::new(DeltaEncoding),
+            Self::Absolute => Box::new(AbsoluteEncoding),
+            Self::Hold => Box::new(HoldEncoding),
+            Self::Spline => Box::new(SplineEncoding),
+        }
+    }
+}
+
+/// Per turn changes of angle and thrust, accumulated into commands
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeltaEncoding;
+
+impl Encoding for DeltaEncoding {
+    fn new_random(&self, size: usize, rng: &mut dyn RngCore) -> Chromosome {
+        Chromosome {
+            angles: (0..size).map(|_| rng.gen_range(ANGLE_STEP_RANGE)).collect(),
+            thrusts: (0..size)
+                .map(|_| rng.gen_range(THRUST_STEP_RANGE))
+                .collect(),
+        }
+    }
+
+    fn decode(
+        &self,
+        genes: &Chromosome,
+        initial_angle: Angle,
+        initial_thrust: Thrust,
+    ) -> Chromosome {
+        genes.accumulated(initial_angle, initial_thrust)
+    }
+
+    fn encode(
+        &self,
+        commands: &Chromosome,
+        initial_angle: Angle,
+        initial_thrust: Thrust,
+        size: usize,
+    ) -> Chromosome {
+        Chromosome::from_accumulated(commands, initial_angle, initial_thrust, size)
+    }
+
+    fn crossover(
+        &self,
+        a: &Chromosome,
+        b: &Chromosome,
+        cross_point: f64,
+    ) -> Result<(Chromosome, Chromosome), SolverError> {
+        a.crossover(b, cross_point)
+    }
+
+    fn mutate_gene(&self, genes: &mut Chromosome, id: usize, rng: &mut dyn RngCore) {
+        genes.angles[id] = rng.gen_range(ANGLE_STEP_RANGE);
+        genes.thrusts[id] = rng.gen_range(THRUST_STEP_RANGE);
+    }
+
+    fn gene_ranges(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        (ANGLE_STEP_RANGE, THRUST_STEP_RANGE)
+    }
+
+    /// New genes don't change the command
+    fn resize(&self, genes: &mut Chromosome, size: usize) {
+        genes.angles.resize(size, 0);
+        genes.thrusts.resize(size, 0);
+    }
+}
+
+/// Absolute command of each turn
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AbsoluteEncoding;
+
+impl Encoding for AbsoluteEncoding {
+    /// Random walk, like accumulated random deltas
+    fn new_random(&self, size: usize, rng: &mut dyn RngCore) -> Chromosome {
+        let mut walk = |range: RangeInclusive<i32>, step: RangeInclusive<i32>| {
+            let mut value = rng.gen_range(range.clone());
+            (0..size)
+                .map(|_| {
+                    value = clamp(value + rng.gen_range(step.clone()), range.clone());
+                    value
+                })
+                .collect()
+        };
+        Chromosome {
+            angles: walk(ANGLE_RANGE, ANGLE_STEP_RANGE),
+            thrusts: walk(THRUST_RANGE, THRUST_STEP_RANGE),
+        }
+    }
+
+    fn decode(&self, genes: &Chromosome, _: Angle, _: Thrust) -> Chromosome {
+        clamped_commands(genes)
+    }
+
+    fn encode(&self, commands: &Chromosome, _: Angle, _: Thrust, size: usize) -> Chromosome {
+        resampled(commands, 1, size)
+    }
+
+    fn crossover(
+        &self,
+        a: &Chromosome,
+        b: &Chromosome,
+        cross_point: f64,
+    ) -> Result<(Chromosome, Chromosome), SolverError> {
+        blended(a, b, cross_point)
+    }
+
+    /// Shifts the command by a random step
+    fn mutate_gene(&self, genes: &mut Chromosome, id: usize, rng: &mut dyn RngCore) {
+        shift_command(genes, id, rng);
+    }
+
+    fn gene_ranges(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        (ANGLE_RANGE, THRUST_RANGE)
+    }
+}
+
+/// Absolute commands held for a variable number of turns. Thrust genes pack the thrust and the
+/// hold duration: `thrust + THRUST_LEVELS * (turns - 1)`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HoldEncoding;
+
+impl HoldEncoding {
+    fn pack(thrust: Thrust, turns: i32) -> i32 {
+        clamp(thrust, THRUST_RANGE) + THRUST_LEVELS * (clamp(turns, 1..=MAX_HOLD) - 1)
+    }
+
+    /// Thrust and number of turns it's held
+    fn unpack(gene: i32) -> (Thrust, i32) {
+        let gene = clamp(gene, 0..=THRUST_LEVELS * MAX_HOLD - 1);
+        (gene % THRUST_LEVELS, gene / THRUST_LEVELS + 1)
+    }
+}
+
+impl Encoding for HoldEncoding {
+    fn new_random(&self, size: usize, rng: &mut dyn RngCore) -> Chromosome {
+        let mut genes = Chromosome {
+            angles: vec![0; size],
+            thrusts: vec![0; size],
+        };
+        (0..size).for_each(|id| self.mutate_gene(&mut genes, id, rng));
+        genes
+    }
+
+    fn decode(&self, genes: &Chromosome, _: Angle, _: Thrust) -> Chromosome {
+        let (angles, thrusts) = genes
+            .angles
+            .iter()
+            .zip(&genes.thrusts)
+            .flat_map(|(angle, gene)| {
+                let (thrust, turns) = Self::unpack(*gene);
+                std::iter::repeat_n((clamp(*angle, ANGLE_RANGE), thrust), turns as usize)
+            })
+            .unzip();
+        Chromosome { angles, thrusts }
+    }
+
+    /// Runs of equal commands become single genes
+    fn encode(&self, commands: &Chromosome, _: Angle, _: Thrust, size: usize) -> Chromosome {
+        let mut runs: Vec<(Angle, Thrust, i32)> = Vec::new();
+        for (angle, thrust) in commands.angles.iter().zip(&commands.thrusts) {
+            match runs.last_mut() {
+                Some((a, t, turns)) if a == angle && t == thrust && *turns < MAX_HOLD => {
+                    *turns += 1
+                }
+                _ => runs.push((*angle, *thrust, 1)),
+            }
+        }
+        let last = runs.last().map_or((0, 0, 1), |(a, t, _)| (*a, *t, 1));
+        runs.resize(size.max(runs.len()), last);
+        runs.truncate(size);
+        Chromosome {
+            angles: runs.iter().map(|(angle, _, _)| *angle).collect(),
+            thrusts: runs
+                .iter()
+                .map(|(_, thrust, turns)| Self::pack(*thrust, *turns))
+                .collect(),
+        }
+    }
+
+    /// One-point crossover, blending genes would mix up hold durations
+    fn crossover(
+        &self,
+        a: &Chromosome,
+        b: &Chromosome,
+        cross_point: f64,
+    ) -> Result<(Chromosome, Chromosome), SolverError> {
+        let error = |source| SolverError::Crossover {
+            genes: "hold genes",
+            source,
+        };
+        if a.angles.len() != b.angles.len() || a.thrusts.len() != b.thrusts.len() {
+            return Err(error(CrossoverError::SizeMismatch {
+                a: a.angles.len(),
+                b: b.angles.len(),
+            }));
+        } else if !(0f64..=1f64).contains(&cross_point) {
+            return Err(error(CrossoverError::CrossPointOutOfRange(cross_point)));
+        }
+        let point = (cross_point * a.angles.len() as f64).round() as usize;
+        let spliced = |a: &[i32], b: &[i32]| [&a[..point], &b[point..]].concat();
+        Ok((
+            Chromosome {
+                angles: spliced(&a.angles, &b.angles),
+                thrusts: spliced(&a.thrusts, &b.thrusts),
+            },
+            Chromosome {
+                angles: spliced(&b.angles, &a.angles),
+                thrusts: spliced(&b.thrusts, &a.thrusts),
+            },
+        ))
+    }
+
+    fn mutate_gene(&self, genes: &mut Chromosome, id: usize, rng: &mut dyn RngCore) {
+        genes.angles[id] = rng.gen_range(ANGLE_RANGE);
+        genes.thrusts[id] = Self::pack(rng.gen_range(THRUST_RANGE), rng.gen_range(1..=MAX_HOLD));
+    }
+
+    fn gene_ranges(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        (ANGLE_RANGE, 0..=THRUST_LEVELS * MAX_HOLD - 1)
+    }
+
+    fn genes_used(&self, genes: &Chromosome, turns: usize) -> usize {
+        let mut flown = 0;
+        genes
+            .thrusts
+            .iter()
+            .take_while(|gene| {
+                let used = flown < turns;
+                flown += Self::unpack(**gene).1 as usize;
+                used
+            })
+            .count()
+    }
+}
+
+/// Absolute commands every [`SPLINE_STEP`] turns, interpolated with a Catmull-Rom spline in
+/// between
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SplineEncoding;
+
+impl Encoding for SplineEncoding {
+    fn new_random(&self, size: usize, rng: &mut dyn RngCore) -> Chromosome {
+        Chromosome {
+            angles: (0..size).map(|_| rng.gen_range(ANGLE_RANGE)).collect(),
+            thrusts: (0..size).map(|_| rng.gen_range(THRUST_RANGE)).collect(),
+        }
+    }
+
+    fn decode(&self, genes: &Chromosome, _: Angle, _: Thrust) -> Chromosome {
+        Chromosome {
+            angles: interpolated(&genes.angles, ANGLE_RANGE),
+            thrusts: interpolated(&genes.thrusts, THRUST_RANGE),
+        }
+    }
+
+    fn encode(&self, commands: &Chromosome, _: Angle, _: Thrust, size: usize) -> Chromosome {
+        resampled(commands, SPLINE_STEP, size)
+    }
+
+    fn crossover(
+        &self,
+        a: &Chromosome,
+        b: &Chromosome,
+        cross_point: f64,
+    ) -> Result<(Chromosome, Chromosome), SolverError> {
+        blended(a, b, cross_point)
+    }
+
+    /// Shifts the control point by a random step
+    fn mutate_gene(&self, genes: &mut Chromosome, id: usize, rng: &mut dyn RngCore) {
+        shift_command(genes, id, rng);
+    }
+
+    fn gene_ranges(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        (ANGLE_RANGE, THRUST_RANGE)
+    }
+
+    /// A turn is interpolated from the control points before and two after it
+    fn genes_used(&self, genes: &Chromosome, turns: usize) -> usize {
+        if turns == 0 {
+            return 0;
+        }
+        ((turns - 1) / SPLINE_STEP + 3).min(genes.angles.len())
+    }
+}
+
+fn clamped_commands(commands: &Chromosome) -> Chromosome {
+    Chromosome {
+        angles: commands
+            .angles
+            .iter()
+            .map(|a| clamp(*a, ANGLE_RANGE))
+            .collect(),
+        thrusts: commands
+            .thrusts
+            .iter()
+            .map(|t| clamp(*t, THRUST_RANGE))
+            .collect(),
+    }
+}
+
+/// Every `step`-th command, padded with the last one up to `size` commands
+fn resampled(commands: &Chromosome, step: usize, size: usize) -> Chromosome {
+    let resample = |values: &[i32]| {
+        let last = values.last().copied().unwrap_or_default();
+        values
+            .iter()
+            .copied()
+            .step_by(step)
+            .chain(std::iter::repeat(last))
+            .take(size)
+            .collect()
+    };
+    clamped_commands(&Chromosome {
+        angles: resample(&commands.angles),
+        thrusts: resample(&commands.thrusts),
+    })
+}
+
+fn blended(
+    a: &Chromosome,
+    b: &Chromosome,
+    cross_point: f64,
+) -> Result<(Chromosome, Chromosome), SolverError> {
+    let (angles_a, angles_b) =
+        crossed(&a.angles, &b.angles, cross_point, |x| clamp(x, ANGLE_RANGE)).map_err(
+            |source| SolverError::Crossover {
+                genes: "angles",
+                source,
+            },
+        )?;
+    let (thrusts_a, thrusts_b) = crossed(&a.thrusts, &b.thrusts, cross_point, |x| {
+        clamp(x, THRUST_RANGE)
+    })
+    .map_err(|source| SolverError::Crossover {
+        genes: "thrusts",
+        source,
+    })?;
+    Ok((
+        Chromosome {
+            angles: angles_a,
+            thrusts: thrusts_a,
+        },
+        Chromosome {
+            angles: angles_b,
+            thrusts: thrusts_b,
+        },
+    ))
+}
+
+fn shift_command(genes: &mut Chromosome, id: usize, rng: &mut dyn RngCore) {
+    genes.angles[id] = clamp(
+        genes.angles[id] + rng.gen_range(ANGLE_STEP_RANGE),
+        ANGLE_RANGE,
+    );
+    genes.thrusts[id] = clamp(
+        genes.thrusts[id] + rng.gen_range(THRUST_STEP_RANGE),
+        THRUST_RANGE,
+    );
+}
+
+/// Catmull-Rom spline through `points`, [`SPLINE_STEP`] values per point, rounded and clamped
+/// into `range`
+fn interpolated(points: &[i32], range: RangeInclusive<i32>) -> Vec<i32> {
+    let point = |i: isize| points[i.clamp(0, points.len() as isize - 1) as usize] as f64;
+    (0..points.len() * SPLINE_STEP)
+        .map(|turn| {
+            let i = (turn / SPLINE_STEP) as isize;
+            let t = (turn % SPLINE_STEP) as f64 / SPLINE_STEP as f64;
+            let (p0, p1, p2, p3) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+            let value = 0.5
+                * (2. * p1
+                    + (p2 - p0) * t
+                    + (2. * p0 - 5. * p1 + 4. * p2 - p3) * t * t
+                    + (3. * p1 - p0 - 3. * p2 + p3) * t * t * t);
+            clamp(value.round() as i32, range.clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod encoding_test {
+    use rand::thread_rng;
+
+    use super::*;
+
+    fn commands() -> Chromosome {
+        Chromosome {
+            angles: vec![-45, -45, -45, -30, -30, -15, 0, 0, 0, 0, 0, 0],
+            thrusts: vec![2, 2, 2, 3, 3, 4, 4, 4, 4, 4, 4, 4],
+        }
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        for kind in [
+            EncodingKind::Delta,
+            EncodingKind::Absolute,
+            EncodingKind::Hold,
+        ] {
+            let encoding = kind.create_encoding();
+            let genes = encoding.encode(&commands(), -45, 2, 20);
+            let decoded = encoding.decode(&genes, -45, 2);
+            assert_eq!(decoded.angles[..12], commands().angles, "{kind:?}");
+            assert_eq!(decoded.thrusts[..12], commands().thrusts, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn hold_genes() {
+        let genes = HoldEncoding.encode(&commands(), 0, 0, 5);
+        assert_eq!(genes.angles, vec![-45, -30, -15, 0, 0]);
+        assert_eq!(
+            genes
+                .thrusts
+                .iter()
+                .map(|g| HoldEncoding::unpack(*g))
+                .collect::<Vec<_>>(),
+            vec![(2, 3), (3, 2), (4, 1), (4, 6), (4, 1)]
+        );
+        assert_eq!(HoldEncoding.decode(&genes, 0, 0).angles.len(), 13);
+        assert_eq!(HoldEncoding.genes_used(&genes, 3), 1);
+        assert_eq!(HoldEncoding.genes_used(&genes, 4), 2);
+        assert_eq!(HoldEncoding.genes_used(&genes, 20), 5);
+    }
+
+    #[test]
+    fn resize_keeps_flight() {
+        for kind in [
+            EncodingKind::Delta,
+            EncodingKind::Absolute,
+            EncodingKind::Hold,
+            EncodingKind::Spline,
+        ] {
+            let encoding = kind.create_encoding();
+            let mut genes = encoding.encode(&commands(), -45, 2, 10);
+            let decoded = encoding.decode(&genes, -45, 2);
+            encoding.resize(&mut genes, 15);
+            assert_eq!(genes.angles.len(), 15, "{kind:?}");
+            let resized = encoding.decode(&genes, -45, 2);
+            assert_eq!(
+                resized.angles[..decoded.angles.len()],
+                decoded.angles,
+                "{kind:?}"
+            );
+            assert_eq!(
+                resized.thrusts[..decoded.thrusts.len()],
+                decoded.thrusts,
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn spline_passes_control_points() {
+        let genes = Chromosome {
+            angles: vec![-60, 0, 30],
+            thrusts: vec![0, 4, 4],
+        };
+        let decoded = SplineEncoding.decode(&genes, 0, 0);
+        assert_eq!(decoded.angles.len(), 3 * SPLINE_STEP);
+        assert_eq!(decoded.angles[0], -60);
+        assert_eq!(decoded.angles[SPLINE_STEP], 0);
+        assert_eq!(decoded.angles[2 * SPLINE_STEP], 30);
+        assert!(decoded.angles[..=SPLINE_STEP]
+            .windows(2)
+            .all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn operators_keep_gene_ranges() {
+        let mut rng = thread_rng();
+        for kind in [
+            EncodingKind::Delta,
+            EncodingKind::Absolute,
+            EncodingKind::Hold,
+            EncodingKind::Spline,
+        ] {
+            let encoding = kind.create_encoding();
+            let (angle_range, thrust_range) = encoding.gene_ranges();
+            let a = encoding.new_random(30, &mut rng);
+            let b = encoding.new_random(30, &mut rng);
+            let (mut c, _) = encoding.crossover(&a, &b, 0.3).unwrap();
+            encoding.mutate(&mut c, 0.5, &mut rng);
+            assert!(c.angles.iter().all(|a| angle_range.contains(a)), "{kind:?}");
+            assert!(
+                c.thrusts.iter().all(|t| thrust_range.contains(t)),
+                "{kind:?}"
+            );
+        }
+    }
+}
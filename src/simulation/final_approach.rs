@@ -0,0 +1,157 @@
+use crate::ConfigError;
+
+use super::{Command, LanderState, PhysicsSettings, Terrain};
+
+#[derive(Clone, Debug)]
+pub struct FinalApproachSettings {
+    /// Engages below this altitude over the landing zone
+    pub altitude: f64,
+    /// Engages when the landing zone would be reached within this many steps at the current
+    /// vertical speed
+    pub time_to_impact: f64,
+    /// Full power is used while descending faster than this
+    pub vertical_speed: f64,
+}
+
+impl Default for FinalApproachSettings {
+    fn default() -> Self {
+        Self {
+            altitude: 0.,
+            time_to_impact: 3.,
+            vertical_speed: 35.,
+        }
+    }
+}
+
+impl FinalApproachSettings {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (key, value) in [
+            ("Altitude", self.altitude),
+            ("TimeToImpact", self.time_to_impact),
+            ("VerticalSpeed", self.vertical_speed),
+        ] {
+            ConfigError::check_range(key, value, 0., f64::INFINITY)?;
+        }
+        Ok(())
+    }
+}
+
+/// Final phase of the flight over the landing zone: the lander is levelled and its descent is
+/// slowed down, whatever the pilot commands.
+///
+/// It's applied by [`super::LanderRunner`] to every controller, a standalone bot can apply it
+/// with [`FinalApproach::command`].
+#[derive(Clone, Debug)]
+pub struct FinalApproach {
+    settings: FinalApproachSettings,
+    power_max: i32,
+}
+
+impl Default for FinalApproach {
+    fn default() -> Self {
+        Self::new(
+            FinalApproachSettings::default(),
+            &PhysicsSettings::default(),
+        )
+    }
+}
+
+impl FinalApproach {
+    /// Final approach slowing down with the highest thrust of `physics`
+    pub fn new(settings: FinalApproachSettings, physics: &PhysicsSettings) -> Self {
+        Self {
+            settings,
+            power_max: physics.power_max,
+        }
+    }
+
+    pub fn engages(&self, lander: &LanderState, terrain: &Terrain) -> bool {
+        let Some((left, right)) = terrain.landing_zone() else {
+            return false;
+        };
+        if !(left.x..=right.x).contains(&lander.x) {
+            return false;
+        }
+        let altitude = lander.y - left.y;
+        altitude < self.settings.altitude
+            || (lander.vy < 0. && altitude / -lander.vy < self.settings.time_to_impact)
+    }
+
+    /// Command replacing pilot's `cmd` if the final approach engages
+    pub fn command(
+        &self,
+        lander: &LanderState,
+        cmd: &Command,
+        terrain: &Terrain,
+    ) -> Option<Command> {
+        if !self.engages(lander, terrain) {
+            return None;
+        }
+        let power = if lander.vy < -self.settings.vertical_speed {
+            self.power_max
+        } else {
+            cmd.power()
+        };
+        Some(Command::new(0., power))
+    }
+}
+
+#[cfg(test)]
+mod final_approach_test {
+    use super::*;
+
+    fn terrain() -> Terrain {
+        Terrain::with_default_limits(vec![0., 1000., 2000.], vec![100., 100., 500.])
+    }
+
+    fn lander(x: f64, y: f64, vy: f64) -> LanderState {
+        LanderState::default()
+            .with_x(x)
+            .with_y(y)
+            .with_vy(vy)
+            .with_angle(30.)
+    }
+
+    #[test]
+    fn engages_over_landing_zone_only() {
+        let final_approach = FinalApproach::new(
+            FinalApproachSettings {
+                altitude: 100.,
+                ..Default::default()
+            },
+            &PhysicsSettings::default(),
+        );
+        assert!(final_approach.engages(&lander(500., 150., 0.), &terrain()));
+        assert!(!final_approach.engages(&lander(500., 250., 0.), &terrain()));
+        assert!(final_approach.engages(&lander(500., 190., -40.), &terrain()));
+        assert!(!final_approach.engages(&lander(1500., 150., -40.), &terrain()));
+    }
+
+    #[test]
+    fn levels_and_slows_down() {
+        let final_approach = FinalApproach::default();
+        let cmd = Command::new(30., 2);
+        let slow = final_approach
+            .command(&lander(500., 150., -20.), &cmd, &terrain())
+            .unwrap();
+        assert_eq!((slow.angle(), slow.power()), (0., 2));
+        let fast = final_approach
+            .command(&lander(500., 150., -38.), &cmd, &terrain())
+            .unwrap();
+        assert_eq!((fast.angle(), fast.power()), (0., 4));
+        assert!(final_approach
+            .command(&lander(500., 1000., -20.), &cmd, &terrain())
+            .is_none());
+        let weaker = FinalApproach::new(
+            FinalApproachSettings::default(),
+            &PhysicsSettings {
+                power_max: 3,
+                ..Default::default()
+            },
+        );
+        let fast = weaker
+            .command(&lander(500., 150., -38.), &cmd, &terrain())
+            .unwrap();
+        assert_eq!(fast.power(), 3);
+    }
+}
@@ -0,0 +1,253 @@
+use super::{LanderHistory, Landing};
+
+/// Scores lander flights of a whole population, the higher the better.
+///
+/// The whole population is passed at once, so implementations may normalize scores across it.
+pub trait FitnessFunction: Send {
+    fn calculate(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>>;
+
+    /// Fitness of every lander comparable between generations, detecting stagnation and given to
+    /// optimizers remembering fitness. Defaults to [`absolute_fitness`] with default
+    /// [`LandingScores`].
+    fn calculate_comparable(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        Some(absolute_fitnesses(&LandingScores::default(), results))
+    }
+}
+
+/// Weight of every landing outcome, scaling its normalized error score
+#[derive(Clone, Debug)]
+pub struct LandingScores {
+    pub correct: f64,
+    pub wrong_terrain: f64,
+    pub not_vertical: f64,
+    pub too_fast_vertical: f64,
+    pub too_fast_horizontal: f64,
+}
+
+impl Default for LandingScores {
+    fn default() -> Self {
+        Self {
+            correct: 1.,
+            wrong_terrain: 0.3,
+            not_vertical: 0.9,
+            too_fast_vertical: 0.7,
+            too_fast_horizontal: 0.5,
+        }
+    }
+}
+
+impl LandingScores {
+    fn score(&self, state: &Landing) -> f64 {
+        match state {
+            Landing::Correct => self.correct,
+            Landing::WrongTerrain { .. } => self.wrong_terrain,
+            Landing::NotVertical { .. } => self.not_vertical,
+            Landing::TooFastVertical { .. } => self.too_fast_vertical,
+            Landing::TooFastHorizontal { .. } => self.too_fast_horizontal,
+        }
+    }
+}
+
+/// Min-max normalized landing errors weighted by landing outcome, see [`calculate_fitness`]
+#[derive(Clone, Debug, Default)]
+pub struct DefaultFitness {
+    pub scores: LandingScores,
+}
+
+impl DefaultFitness {
+    pub fn with_scores(scores: LandingScores) -> Self {
+        Self { scores }
+    }
+}
+
+impl FitnessFunction for DefaultFitness {
+    fn calculate(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        let (landings, fuel_left): (Vec<_>, Vec<_>) = results
+            .iter()
+            .map(|(history, landing)| ((*landing).clone(), fuel_left(history)))
+            .unzip();
+        weighted_fitness(&self.scores, &landings, &fuel_left)
+    }
+
+    fn calculate_comparable(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        Some(absolute_fitnesses(&self.scores, results))
+    }
+}
+
+fn absolute_fitnesses(scores: &LandingScores, results: &[(&LanderHistory, &Landing)]) -> Vec<f64> {
+    results
+        .iter()
+        .map(|(history, landing)| absolute_fitness(scores, landing, fuel_left(history)))
+        .collect()
+}
+
+/// Fraction of the initial fuel left at the end of the flight
+pub fn fuel_left(history: &LanderHistory) -> f64 {
+    match (history.initial_state(), history.last_state()) {
+        (Some(initial), Some(last)) if initial.fuel > 0 => last.fuel as f64 / initial.fuel as f64,
+        _ => 0.,
+    }
+}
+
+/// Fitness of a single landing comparable between generations, unlike the population-normalized
+/// [`calculate_fitness`]: crashes score their weight scaled by `1 / (1 + error)` of their raw error,
+/// correct landings 1 plus the fraction of fuel left
+pub fn absolute_fitness(scores: &LandingScores, landing: &Landing, fuel_left: f64) -> f64 {
+    let error = match landing {
+        Landing::Correct => return scores.correct + fuel_left.clamp(0., 1.),
+        Landing::WrongTerrain { dist } => *dist,
+        Landing::NotVertical { error_abs }
+        | Landing::TooFastVertical { error_abs }
+        | Landing::TooFastHorizontal { error_abs } => *error_abs,
+    };
+    scores.score(landing) / (1. + error.abs())
+}
+
+#[derive(Default)]
+struct MaxErrors {
+    angle_error: Option<(f64, f64)>,
+    horizontal_speed_error: Option<(f64, f64)>,
+    vertical_speed_error: Option<(f64, f64)>,
+    terrain_dist_error: Option<(f64, f64)>,
+}
+
+fn update_min_max(a: &mut Option<(f64, f64)>, b: f64) {
+    *a = match a {
+        None => Some((b, b)),
+        Some((existing_min, existing_max)) => Some((existing_min.min(b), existing_max.max(b))),
+    };
+}
+
+fn get_min_max_errors<'a>(landing_results: impl Iterator<Item = &'a Landing>) -> MaxErrors {
+    landing_results.fold(MaxErrors::default(), |mut errors, landing_result| {
+        match landing_result {
+            Landing::NotVertical { error_abs, .. } => {
+                update_min_max(&mut errors.angle_error, *error_abs)
+            }
+            Landing::WrongTerrain { dist, .. } => {
+                update_min_max(&mut errors.terrain_dist_error, *dist)
+            }
+            Landing::TooFastHorizontal { error_abs, .. } => {
+                update_min_max(&mut errors.horizontal_speed_error, *error_abs)
+            }
+            Landing::TooFastVertical { error_abs, .. } => {
+                update_min_max(&mut errors.vertical_speed_error, *error_abs)
+            }
+            Landing::Correct => (),
+        };
+        errors
+    })
+}
+
+/// Calculates fitness of each landing result with default [`LandingScores`], the higher the better.
+///
+/// Crashed landings score below 1. Correct landings score 1 plus the fraction of fuel left
+/// (`fuel_left`, in range [0,1]), so once some lander lands the search keeps optimizing for fuel.
+pub fn calculate_fitness(landing_results: &[Landing], fuel_left: &[f64]) -> Option<Vec<f64>> {
+    weighted_fitness(&LandingScores::default(), landing_results, fuel_left)
+}
+
+fn weighted_fitness(
+    scores: &LandingScores,
+    landing_results: &[Landing],
+    fuel_left: &[f64],
+) -> Option<Vec<f64>> {
+    if landing_results.len() != fuel_left.len() {
+        return None;
+    }
+    let max_errors = get_min_max_errors(landing_results.iter());
+    let normalized_score = |value, (min, max)| {
+        assert!(max >= min);
+        if max == min {
+            0.
+        } else {
+            (value - min) / (max - min)
+        }
+    };
+    let base_score = |result: &Landing| {
+        Some(match result {
+            Landing::Correct => 0.,
+            Landing::NotVertical { error_abs, .. } => {
+                normalized_score(*error_abs, max_errors.angle_error?)
+            }
+            Landing::TooFastHorizontal { error_abs, .. } => {
+                normalized_score(*error_abs, max_errors.horizontal_speed_error?)
+            }
+            Landing::TooFastVertical { error_abs, .. } => {
+                normalized_score(*error_abs, max_errors.vertical_speed_error?)
+            }
+            Landing::WrongTerrain { dist } => {
+                normalized_score(*dist, max_errors.terrain_dist_error?)
+            }
+        })
+    };
+
+    landing_results
+        .iter()
+        .zip(fuel_left)
+        .map(|(result, fuel_left)| {
+            Some(match result {
+                Landing::Correct => scores.score(result) + fuel_left.clamp(0., 1.),
+                _ => (1. - base_score(result)?) * scores.score(result),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod fitness_test {
+    use super::*;
+    use crate::LanderState;
+
+    #[test]
+    fn correct_landings_ranked_by_fuel() {
+        let fitness =
+            calculate_fitness(&[Landing::Correct, Landing::Correct], &[0.2, 0.6]).unwrap();
+        assert!(fitness[1] > fitness[0]);
+    }
+
+    #[test]
+    fn correct_landing_beats_crash() {
+        let fitness = calculate_fitness(
+            &[Landing::NotVertical { error_abs: 1. }, Landing::Correct],
+            &[1., 0.],
+        )
+        .unwrap();
+        assert!(fitness[1] > fitness[0]);
+    }
+
+    #[test]
+    fn absolute_fitness_independent_of_population() {
+        let scores = LandingScores::default();
+        let near = Landing::TooFastVertical { error_abs: 2. };
+        let far = Landing::TooFastVertical { error_abs: 20. };
+        // both are the best of their populations, so they score the same normalized fitness
+        assert_eq!(
+            calculate_fitness(std::slice::from_ref(&near), &[0.]),
+            calculate_fitness(std::slice::from_ref(&far), &[0.])
+        );
+        assert!(absolute_fitness(&scores, &near, 0.) > absolute_fitness(&scores, &far, 0.));
+        assert!(
+            absolute_fitness(&scores, &Landing::Correct, 0.) > absolute_fitness(&scores, &near, 0.)
+        );
+    }
+
+    #[test]
+    fn mismatched_fuel() {
+        assert!(calculate_fitness(&[Landing::Correct], &[]).is_none());
+    }
+
+    #[test]
+    fn custom_scores() {
+        let history = LanderHistory::with_initial_state(LanderState::default());
+        let wrong_terrain = Landing::WrongTerrain { dist: 1. };
+        let not_vertical = Landing::NotVertical { error_abs: 1. };
+        let fitness = DefaultFitness::with_scores(LandingScores {
+            wrong_terrain: 0.95,
+            ..Default::default()
+        })
+        .calculate(&[(&history, &wrong_terrain), (&history, &not_vertical)])
+        .unwrap();
+        assert!(fitness[0] > fitness[1]);
+    }
+}
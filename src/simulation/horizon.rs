@@ -0,0 +1,88 @@
+use crate::ConfigError;
+
+/// Growing and shrinking of the chromosome to the length of the flights
+#[derive(Clone, Debug)]
+pub struct AdaptiveHorizonSettings {
+    /// Fraction of landers running out of genes above which the chromosome grows
+    pub grow_threshold: f64,
+    /// Relative growth of the chromosome, also the margin kept above the longest flight when it
+    /// shrinks
+    pub growth: f64,
+    /// The chromosome shrinks when the longest flight uses less than this fraction of its genes
+    pub shrink_threshold: f64,
+    pub min_size: usize,
+    pub max_size: usize,
+}
+
+impl Default for AdaptiveHorizonSettings {
+    fn default() -> Self {
+        Self {
+            grow_threshold: 0.05,
+            growth: 0.25,
+            shrink_threshold: 0.5,
+            min_size: 20,
+            max_size: 1000,
+        }
+    }
+}
+
+impl AdaptiveHorizonSettings {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        ConfigError::check_probability("GrowThreshold", self.grow_threshold)?;
+        ConfigError::check_probability("ShrinkThreshold", self.shrink_threshold)?;
+        ConfigError::check_range("Growth", self.growth, f64::MIN_POSITIVE, f64::INFINITY)?;
+        ConfigError::check_range("MinSize", self.min_size as f64, 1., self.max_size as f64)
+    }
+}
+
+/// Genes used by a lander during its flight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeneUsage {
+    pub used: usize,
+    /// The lander flew past its last gene, repeating the last command
+    pub exhausted: bool,
+}
+
+/// Chromosome size for the next generation given the gene usage of the landers of the last one
+pub fn adapted_horizon(
+    settings: &AdaptiveHorizonSettings,
+    size: usize,
+    usage: &[GeneUsage],
+) -> usize {
+    if usage.is_empty() {
+        return size;
+    }
+    let exhausted = usage.iter().filter(|u| u.exhausted).count();
+    let longest = usage.iter().map(|u| u.used).max().unwrap_or_default();
+    let with_margin = |size: usize| (size as f64 * (1. + settings.growth)).ceil() as usize;
+    let adapted = if exhausted as f64 > settings.grow_threshold * usage.len() as f64 {
+        with_margin(size).max(size + 1)
+    } else if (longest as f64) < settings.shrink_threshold * size as f64 {
+        with_margin(longest)
+    } else {
+        size
+    };
+    adapted.clamp(settings.min_size, settings.max_size.max(settings.min_size))
+}
+
+#[cfg(test)]
+mod horizon_test {
+    use super::*;
+
+    fn usage(used: usize, exhausted: bool) -> GeneUsage {
+        GeneUsage { used, exhausted }
+    }
+
+    #[test]
+    fn grows_shrinks_and_keeps() {
+        let settings = AdaptiveHorizonSettings::default();
+        let grown = [usage(100, true), usage(60, false)];
+        assert_eq!(adapted_horizon(&settings, 100, &grown), 125);
+        let kept = [usage(80, false), usage(60, false)];
+        assert_eq!(adapted_horizon(&settings, 100, &kept), 100);
+        let shrunk = [usage(40, false), usage(10, false)];
+        assert_eq!(adapted_horizon(&settings, 100, &shrunk), 50);
+        assert_eq!(adapted_horizon(&settings, 100, &[usage(1, false)]), 20);
+        assert_eq!(adapted_horizon(&settings, 900, &[usage(900, true)]), 1000);
+    }
+}
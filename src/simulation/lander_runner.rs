@@ -1,19 +1,8 @@
-use std::fmt::Display;
-
 pub use crate::simulation::*;
+use crate::SimulationError;
 
-#[derive(Debug)]
-pub enum Error {
-    InconsistentState,
-    CommandGetError { id: usize, sub_id: usize },
-    SimulationError(SimulationError),
-}
-
-impl Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
+/// Steps after which [`LanderRunner::simulate`] gives up a flight that doesn't end
+const MAX_FLIGHT_STEPS: usize = 10_000;
 
 #[derive(Debug)]
 pub enum ExecutionStatus {
@@ -27,15 +16,10 @@ pub enum FlightState {
     Landed(Landing),
 }
 
-impl From<SimulationError> for Error {
-    fn from(val: SimulationError) -> Self {
-        Error::SimulationError(val)
-    }
-}
-
 struct LanderStateCalculation {
     physics: Physics,
     collision_checker: CollisionChecker,
+    final_approach: Option<FinalApproach>,
 }
 
 impl LanderStateCalculation {
@@ -43,18 +27,31 @@ impl LanderStateCalculation {
         Self {
             physics,
             collision_checker,
+            final_approach: None,
         }
     }
 
+    /// New lander state after executing `cmd`, or the final approach command if it engages
+    /// (returned as well).
+    pub fn step(
+        &self,
+        lander: &LanderState,
+        cmd: Command,
+    ) -> Result<(LanderState, FlightState, Option<Command>), SimulationError> {
+        let final_approach_cmd = self.final_approach.as_ref().and_then(|final_approach| {
+            final_approach.command(lander, &cmd, self.collision_checker.terrain())
+        });
+        let (lander, flight_state) =
+            self.calculate_new_lander_state(lander, final_approach_cmd.clone().unwrap_or(cmd))?;
+        Ok((lander, flight_state, final_approach_cmd))
+    }
+
     pub fn calculate_new_lander_state(
         &self,
         lander: &LanderState,
         cmd: Command,
-    ) -> Result<(LanderState, FlightState), Error> {
-        let new_lander_state = self
-            .physics
-            .iterate(lander.clone(), cmd)
-            .map_err(<SimulationError as std::convert::Into<Error>>::into)?;
+    ) -> Result<(LanderState, FlightState), SimulationError> {
+        let new_lander_state = self.physics.iterate(lander.clone(), cmd)?;
         if let Some(((x, y), landing)) = self.collision_checker.check(lander, &new_lander_state) {
             Ok((
                 LanderState {
@@ -73,8 +70,8 @@ impl LanderStateCalculation {
 pub struct LanderRunner {
     states: Vec<FlightState>,
     landers: Vec<LanderState>,
+    final_approach_steps: Vec<Option<usize>>,
     lander_state_calculator: LanderStateCalculation,
-    angle_step: f64,
     iteration_id: usize,
     correct_landing_id: Option<usize>,
 }
@@ -86,12 +83,11 @@ impl LanderRunner {
         physics: Physics,
         collision_checker: CollisionChecker,
     ) -> Self {
-        let angle_step = collision_checker.angle_step;
         Self {
             lander_state_calculator: LanderStateCalculation::new(physics, collision_checker),
             states: vec![FlightState::Flying; num_of_landers],
             landers: vec![initial_lander_state; num_of_landers],
-            angle_step,
+            final_approach_steps: vec![None; num_of_landers],
             iteration_id: 0,
             correct_landing_id: None,
         }
@@ -100,13 +96,34 @@ impl LanderRunner {
     pub fn reinitialize(&mut self, initial_lander_state: LanderState) {
         self.states = vec![FlightState::Flying; self.num_of_landers()];
         self.landers = vec![initial_lander_state; self.num_of_landers()];
+        self.final_approach_steps = vec![None; self.num_of_landers()];
         self.iteration_id = 0;
+        self.correct_landing_id = None;
+    }
+
+    /// Applies the final approach to every lander flown with `physics`, none without `settings`
+    pub fn with_final_approach(
+        self,
+        settings: Option<FinalApproachSettings>,
+        physics: &PhysicsSettings,
+    ) -> Self {
+        Self {
+            lander_state_calculator: LanderStateCalculation {
+                final_approach: settings.map(|settings| FinalApproach::new(settings, physics)),
+                ..self.lander_state_calculator
+            },
+            ..self
+        }
     }
 
     pub fn num_of_landers(&self) -> usize {
         self.landers.len()
     }
 
+    pub fn collision_checker(&self) -> &CollisionChecker {
+        &self.lander_state_calculator.collision_checker
+    }
+
     pub fn current_landers_states(&self) -> impl Iterator<Item = &LanderState> {
         self.landers.iter()
     }
@@ -115,44 +132,44 @@ impl LanderRunner {
         self.states.iter()
     }
 
-    pub fn iterate(&mut self, population: &mut [Chromosome]) -> Result<ExecutionStatus, Error> {
+    /// Step at which the final approach engaged for each lander, if it did
+    pub fn final_approach_steps(&self) -> impl Iterator<Item = &Option<usize>> {
+        self.final_approach_steps.iter()
+    }
+
+    pub fn terrain(&self) -> &Terrain {
+        self.collision_checker().terrain()
+    }
+
+    /// Executes a single step of every flying lander, lander `id` piloted by `controllers[id]`
+    pub fn iterate<C: Controller>(
+        &mut self,
+        controllers: &mut [C],
+    ) -> Result<ExecutionStatus, SimulationError> {
         assert_eq!(self.states.len(), self.landers.len());
-        assert_eq!(self.states.len(), population.len());
+        assert_eq!(self.states.len(), controllers.len());
 
         let mut picked_any = false;
+        let calculator = &self.lander_state_calculator;
 
-        for (id, ((lander, flight_state), angle_thrust)) in self
+        for (id, (((lander, flight_state), final_approach_step), controller)) in self
             .landers
             .iter_mut()
             .zip(self.states.iter_mut())
-            .zip(
-                population
-                    .iter_mut()
-                    .map(|chromosome| get_id_or_last(chromosome, self.iteration_id)),
-            )
+            .zip(self.final_approach_steps.iter_mut())
+            .zip(controllers.iter_mut())
             .enumerate()
         {
             if let FlightState::Flying = *flight_state {
                 picked_any = true;
-                let (angle, thrust) = angle_thrust.ok_or(Error::CommandGetError {
-                    id,
-                    sub_id: self.iteration_id,
-                })?;
-                let (new_lander_state, new_flight_state) = match self
-                    .lander_state_calculator
-                    .calculate_new_lander_state(lander, Command::new(*angle as f64, *thrust))?
-                {
-                    (_, FlightState::Landed(Landing::NotVertical { error_abs, .. }))
-                        if error_abs <= self.angle_step =>
-                    {
-                        *angle = 0;
-                        self.lander_state_calculator.calculate_new_lander_state(
-                            lander,
-                            Command::new(*angle as f64, *thrust),
-                        )? // recalculate for new angle
-                    }
-                    other => other,
-                };
+                let (new_lander_state, new_flight_state) = Self::step(
+                    calculator,
+                    controller,
+                    self.iteration_id,
+                    lander,
+                    final_approach_step,
+                )
+                .map_err(|e| e.with_lander_id(id))?;
                 *lander = new_lander_state;
                 *flight_state = new_flight_state;
                 if let FlightState::Landed(Landing::Correct) = flight_state {
@@ -168,21 +185,115 @@ impl LanderRunner {
             Ok(ExecutionStatus::Finished(self.correct_landing_id))
         }
     }
-}
-fn get_id_or_last(chromosome: &mut Chromosome, index: usize) -> Option<(&mut i32, &mut i32)> {
-    if index < chromosome.angles.len() {
-        Some((
-            chromosome.angles.get_mut(index)?,
-            chromosome.thrusts.get_mut(index)?,
-        )) // Safe because we checked bounds
-    } else {
-        Some((
-            chromosome.angles.last_mut()?,
-            chromosome.thrusts.last_mut()?,
-        )) // Fallback to last_mut
+
+    /// Simulates a single lander flight until it lands, independently of the runner's landers
+    ///
+    /// The controller is notified about final approach commands the same way as in
+    /// [`LanderRunner::iterate`], engagement is recorded in the history.
+    pub fn simulate(
+        &self,
+        initial_lander_state: LanderState,
+        controller: &mut impl Controller,
+    ) -> Result<(LanderHistory, Landing), SimulationError> {
+        self.simulate_from(
+            LanderHistory::with_initial_state(initial_lander_state),
+            controller,
+        )
+    }
+
+    /// Continues the flight of `history` from its last state until it lands, like
+    /// [`LanderRunner::simulate`]
+    ///
+    /// Fails with [`SimulationError::FlightTooLong`] if the whole flight exceeds
+    /// `MAX_FLIGHT_STEPS`.
+    pub fn simulate_from(
+        &self,
+        mut history: LanderHistory,
+        controller: &mut impl Controller,
+    ) -> Result<(LanderHistory, Landing), SimulationError> {
+        let mut lander = history
+            .last_state()
+            .ok_or(SimulationError::InconsistentState)?;
+        for sub_id in history.steps()..MAX_FLIGHT_STEPS {
+            let (new_lander_state, flight_state) = Self::step(
+                &self.lander_state_calculator,
+                controller,
+                sub_id,
+                &lander,
+                &mut history.final_approach_step,
+            )?;
+            lander = new_lander_state;
+            history.append_lander_state(&lander);
+            if let FlightState::Landed(landing) = flight_state {
+                return Ok((history, landing));
+            }
+        }
+        Err(SimulationError::FlightTooLong {
+            steps: MAX_FLIGHT_STEPS,
+        })
+    }
+
+    /// Flies a single lander piloted by `controller` until it lands or `max_steps` are done
+    ///
+    /// Returns commands actually executed (within physics limits), history and landing if any.
+    pub fn fly(
+        &self,
+        initial_lander_state: LanderState,
+        max_steps: usize,
+        controller: &mut impl Controller,
+    ) -> Result<(Chromosome, LanderHistory, Option<Landing>), SimulationError> {
+        let mut history = LanderHistory::with_initial_state(initial_lander_state.clone());
+        let mut commands = Chromosome {
+            angles: Vec::new(),
+            thrusts: Vec::new(),
+        };
+        let mut lander = initial_lander_state;
+        for sub_id in 0..max_steps {
+            let (new_lander_state, flight_state) = Self::step(
+                &self.lander_state_calculator,
+                controller,
+                sub_id,
+                &lander,
+                &mut history.final_approach_step,
+            )?;
+            lander = new_lander_state;
+            commands.angles.push(lander.angle.round() as i32);
+            commands.thrusts.push(lander.power);
+            history.append_lander_state(&lander);
+            if let FlightState::Landed(landing) = flight_state {
+                return Ok((commands, history, Some(landing)));
+            }
+        }
+        Ok((commands, history, None))
     }
-}
 
+    fn step(
+        calculator: &LanderStateCalculation,
+        controller: &mut impl Controller,
+        sub_id: usize,
+        lander: &LanderState,
+        final_approach_step: &mut Option<usize>,
+    ) -> Result<(LanderState, FlightState), SimulationError> {
+        let cmd = controller
+            .command(sub_id, lander, calculator.collision_checker.terrain())
+            .ok_or(SimulationError::MissingCommand { id: 0, sub_id })?;
+        let (lander, flight_state, final_approach_cmd) = calculator.step(lander, cmd)?;
+        if let Some(cmd) = final_approach_cmd {
+            controller.overridden(sub_id, &cmd);
+            final_approach_step.get_or_insert(sub_id);
+        }
+        Ok((lander, flight_state))
+    }
+
+    /// Overrides results of lander `id`, e.g. after it was simulated again with [`LanderRunner::simulate`]
+    pub fn set_lander(&mut self, id: usize, history: &LanderHistory, flight_state: FlightState) {
+        if let Some(lander_state) = history.last_state() {
+            self.landers[id] = lander_state;
+        }
+        self.final_approach_steps[id] = history.final_approach_step;
+        self.states[id] = flight_state;
+    }
+}
 #[derive(Clone)]
 pub struct LanderHistory {
     x: Vec<f64>,
@@ -192,6 +303,7 @@ pub struct LanderHistory {
     fuel: Vec<i32>,
     angle: Vec<f64>,
     power: Vec<i32>,
+    final_approach_step: Option<usize>,
 }
 
 impl LanderHistory {
@@ -213,6 +325,7 @@ impl LanderHistory {
             fuel: vec![fuel],
             angle: vec![angle],
             power: vec![power],
+            final_approach_step: None,
         }
     }
 
@@ -247,6 +360,48 @@ impl LanderHistory {
         self.power.push(state.power);
     }
 
+    /// Number of steps flown
+    pub fn steps(&self) -> usize {
+        self.x.len().saturating_sub(1)
+    }
+
+    /// Forgets everything after the first `steps` steps
+    pub fn truncate(&mut self, steps: usize) {
+        self.x.truncate(steps + 1);
+        self.y.truncate(steps + 1);
+        self.vx.truncate(steps + 1);
+        self.vy.truncate(steps + 1);
+        self.fuel.truncate(steps + 1);
+        self.angle.truncate(steps + 1);
+        self.power.truncate(steps + 1);
+        self.final_approach_step = self.final_approach_step.filter(|step| *step < steps);
+    }
+
+    /// Step at which the final approach engaged, if it did
+    pub fn final_approach_step(&self) -> Option<usize> {
+        self.final_approach_step
+    }
+
+    pub fn set_final_approach_step(&mut self, step: Option<usize>) {
+        self.final_approach_step = step;
+    }
+
+    pub fn initial_state(&self) -> Option<LanderState> {
+        self.iter_history().next()
+    }
+
+    pub fn last_state(&self) -> Option<LanderState> {
+        Some(LanderState {
+            x: *self.x.last()?,
+            y: *self.y.last()?,
+            vx: *self.vx.last()?,
+            vy: *self.vy.last()?,
+            fuel: *self.fuel.last()?,
+            angle: *self.angle.last()?,
+            power: *self.power.last()?,
+        })
+    }
+
     pub fn iter_history(&self) -> impl Iterator<Item = LanderState> + '_ {
         self.x
             .iter()
@@ -267,3 +422,33 @@ impl LanderHistory {
             })
     }
 }
+
+#[cfg(test)]
+mod lander_runner_test {
+    use super::*;
+
+    #[test]
+    fn hovering_flight_gives_up() {
+        let terrain = Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]);
+        let runner = LanderRunner::new(
+            LanderState::default(),
+            1,
+            Physics::default().with_g(0.),
+            LandingCriteria::default()
+                .collision_checker(terrain)
+                .unwrap(),
+        );
+        let mut hover = Chromosome {
+            angles: vec![0],
+            thrusts: vec![0],
+        };
+        let lander = LanderState::default().with_x(500.).with_y(1000.);
+
+        assert!(matches!(
+            runner.simulate(lander, &mut hover),
+            Err(SimulationError::FlightTooLong {
+                steps: MAX_FLIGHT_STEPS
+            })
+        ));
+    }
+}
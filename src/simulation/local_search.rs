@@ -0,0 +1,93 @@
+use rand::Rng;
+
+use super::{Chromosome, Encoding, FitnessFunction, LanderHistory, Landing};
+use crate::{Error, SimulationError, SolverError};
+
+/// Genes close to the end of the flight are picked more often, that's where near-miss landings
+/// are decided
+const FLIGHT_END_GENES: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct LocalSearchSettings {
+    /// Number of the best chromosomes refined after each generation
+    pub top_k: usize,
+    /// Number of neighbours simulated for each refined chromosome
+    pub max_steps: usize,
+}
+
+/// Simulated chromosome
+#[derive(Clone)]
+pub struct Candidate {
+    pub genes: Chromosome,
+    /// Absolute commands executed during the flight
+    pub commands: Chromosome,
+    pub history: LanderHistory,
+    pub landing: Landing,
+}
+
+/// Hill-climbs from `start`, each step simulating a neighbour with a single gene pair changed and
+/// keeping it if `fitness` prefers it over the current candidate.
+///
+/// Returns the best candidate found and the number of simulations done.
+pub fn hill_climb(
+    start: Candidate,
+    settings: &LocalSearchSettings,
+    fitness: &dyn FitnessFunction,
+    encoding: &dyn Encoding,
+    mut simulate: impl FnMut(Chromosome) -> Result<Candidate, SimulationError>,
+) -> Result<(Candidate, usize), Error> {
+    let mut current = start;
+    for _ in 0..settings.max_steps {
+        let used_genes = current.history.iter_history().count() - 1;
+        let candidate = simulate(neighbour(&current.genes, used_genes, encoding))?;
+        let scores = fitness
+            .calculate(&[
+                (&current.history, &current.landing),
+                (&candidate.history, &candidate.landing),
+            ])
+            .ok_or(SolverError::Fitness)?;
+        if scores[1] > scores[0] {
+            current = candidate;
+        }
+    }
+    Ok((current, settings.max_steps))
+}
+
+/// Copy of `genes` with a single gene pair mutated, picked among the first `used_genes`
+fn neighbour(genes: &Chromosome, used_genes: usize, encoding: &dyn Encoding) -> Chromosome {
+    let mut neighbour = genes.clone();
+    if genes.angles.is_empty() {
+        return neighbour;
+    }
+    let used_genes = used_genes.clamp(1, genes.angles.len());
+    let mut rng = rand::thread_rng();
+    let id = if rng.gen_bool(0.5) {
+        rng.gen_range(used_genes.saturating_sub(FLIGHT_END_GENES)..used_genes)
+    } else {
+        rng.gen_range(0..used_genes)
+    };
+    encoding.mutate_gene(&mut neighbour, id, &mut rng);
+    neighbour
+}
+
+#[cfg(test)]
+mod local_search_test {
+    use super::*;
+    use crate::simulation::DeltaEncoding;
+
+    #[test]
+    fn neighbour_changes_used_genes_only() {
+        let genes = Chromosome {
+            angles: vec![100; 30],
+            thrusts: vec![100; 30],
+        };
+        for _ in 0..100 {
+            let neighbour = neighbour(&genes, 5, &DeltaEncoding);
+            let changed: Vec<_> = (0..30)
+                .filter(|i| neighbour.angles[*i] != 100 || neighbour.thrusts[*i] != 100)
+                .collect();
+            assert_eq!(changed.len(), 1);
+            assert!(changed[0] < 5);
+        }
+    }
+}
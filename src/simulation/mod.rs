@@ -1,9 +1,49 @@
+mod annealing;
+mod autopilot;
+mod cache;
+mod checkpoint;
+mod cma_es;
 mod collision;
+mod controller;
+mod diversity;
+mod encoding;
+mod final_approach;
+mod fitness;
+mod horizon;
 mod lander_runner;
+mod local_search;
+mod mpc;
+mod neuro;
+mod optimizer;
+mod pareto;
+mod particle_swarm;
 mod physics;
+mod planner;
+mod report;
 mod solver;
+mod stagnation;
 
+pub use annealing::*;
+pub use autopilot::*;
+pub use cache::*;
+pub use checkpoint::*;
+pub use cma_es::*;
 pub use collision::*;
+pub use controller::*;
+pub use diversity::*;
+pub use encoding::*;
+pub use final_approach::*;
+pub use fitness::*;
+pub use horizon::*;
 pub use lander_runner::*;
+pub use local_search::*;
+pub use mpc::*;
+pub use neuro::*;
+pub use optimizer::*;
+pub use pareto::*;
+pub use particle_swarm::*;
 pub use physics::*;
+pub use planner::*;
+pub use report::*;
 pub use solver::*;
+pub use stagnation::*;
@@ -0,0 +1,286 @@
+use rand::Rng;
+
+use super::{
+    solver::clamp, Chromosome, CollisionChecker, Command, Controller, LanderHistory, LanderRunner,
+    LanderState, Landing, Physics, Terrain,
+};
+use crate::TerrainError;
+
+/// Cost of a correct landing predicted within the horizon, below any flight still in progress
+const LANDING_COST: f64 = -100.;
+/// Decelerations assumed when checking whether the lander can still stop in time
+const HORIZONTAL_BRAKING: f64 = 1.;
+const VERTICAL_BRAKING: f64 = 0.3;
+const MAX_CRUISE_SPEED: f64 = 60.;
+
+#[derive(Clone, Debug)]
+pub struct MpcSettings {
+    /// Number of predicted steps
+    pub horizon: usize,
+    /// Number of plan perturbations tried each step
+    pub iterations: usize,
+    /// Height to keep above the terrain between the lander and the landing zone
+    pub clearance: f64,
+    /// Weight of the horizontal distance to the landing zone (per 1000 m), the missing clearance
+    /// counts tenfold
+    pub distance_weight: f64,
+    /// Weight of the speed exceeding what can still be braked in time (per 10 m/s)
+    pub speed_weight: f64,
+    /// Weight of the angle (per 90 degrees)
+    pub angle_weight: f64,
+    /// Weight of the fuel used (per 100 units)
+    pub fuel_weight: f64,
+}
+
+impl Default for MpcSettings {
+    fn default() -> Self {
+        Self {
+            horizon: 20,
+            iterations: 100,
+            clearance: 200.,
+            distance_weight: 1.,
+            speed_weight: 1.,
+            angle_weight: 0.1,
+            fuel_weight: 0.05,
+        }
+    }
+}
+
+/// Model predictive control autopilot.
+///
+/// Each step it improves a plan of absolute commands over a short horizon by hill climbing on
+/// its predicted cost, executes the first command and shifts the plan. Avoiding crashes predicted by
+/// the collision checker is a constraint: candidates crashing within the horizon are rejected, the
+/// plan only crashes while no candidate avoids it, then ranked by the crash error.
+pub struct MpcController {
+    model: LanderRunner,
+    settings: MpcSettings,
+    plan: Chromosome,
+}
+
+impl MpcController {
+    /// Controller predicting flights with `physics` and `collision_checker`
+    pub fn new(
+        physics: Physics,
+        collision_checker: CollisionChecker,
+        settings: MpcSettings,
+    ) -> Self {
+        Self {
+            model: LanderRunner::new(LanderState::default(), 1, physics, collision_checker),
+            settings,
+            plan: Chromosome {
+                angles: Vec::new(),
+                thrusts: Vec::new(),
+            },
+        }
+    }
+
+    pub fn try_with_default_model(
+        terrain: &Terrain,
+        settings: MpcSettings,
+    ) -> Result<Self, TerrainError> {
+        let collision_checker = CollisionChecker::try_with_default_limits(terrain.clone())
+            .ok_or(TerrainError::NoLandingZone)?;
+        Ok(Self::new(Physics::default(), collision_checker, settings))
+    }
+
+    /// Predicted outcome of flying `plan` from `lander`
+    fn predict(&self, lander: &LanderState, plan: &Chromosome) -> Option<Prediction> {
+        let (_, history, landing) = self
+            .model
+            .fly(lander.clone(), self.settings.horizon, &mut plan.clone())
+            .ok()?;
+        let last = history.last_state()?;
+        let fuel_used = (lander.fuel - last.fuel) as f64 / 100. * self.settings.fuel_weight;
+        Some(match landing {
+            Some(Landing::Correct) => Prediction::Flight(LANDING_COST + fuel_used),
+            Some(crash) => Prediction::Crash(crash_error(&crash)),
+            None => Prediction::Flight(
+                self.flight_cost(&last) + fuel_used + self.trajectory_cost(&history),
+            ),
+        })
+    }
+
+    /// Cost of the predicted state at the end of the horizon
+    fn flight_cost(&self, lander: &LanderState) -> f64 {
+        let terrain = self.model.terrain();
+        let Some((left, right)) = terrain.landing_zone() else {
+            return 0.;
+        };
+        let margin = (right.x - left.x) * 0.1;
+        let (left_x, right_x) = (left.x + margin, right.x - margin);
+        let dx = if lander.x < left_x {
+            left_x - lander.x
+        } else if lander.x > right_x {
+            lander.x - right_x
+        } else {
+            0.
+        };
+        let height = lander.y - terrain.ground_below(lander.x, lander.y).unwrap_or(lander.y);
+        let altitude = (lander.y - left.y).max(0.);
+        let target_x = (left.x + right.x) / 2.;
+        let obstacle_depth = if dx == 0. {
+            0.
+        } else {
+            (terrain.max_height_between(lander.x, target_x) + self.settings.clearance - lander.y)
+                .max(0.)
+        };
+
+        // speeds that can still be braked before reaching the zone and the ground
+        let max_vx = (2. * HORIZONTAL_BRAKING * dx)
+            .sqrt()
+            .clamp(5., MAX_CRUISE_SPEED);
+        let max_vy = if dx == 0. {
+            (2. * VERTICAL_BRAKING * height.max(0.)).sqrt().max(30.)
+        } else {
+            // keep enough height to stop above the clearance
+            (2. * VERTICAL_BRAKING * (height - self.settings.clearance).max(0.))
+                .sqrt()
+                .max(15.)
+        };
+        let toward_zone = if dx == 0. { 0. } else { target_x - lander.x };
+        let vx_excess = if lander.vx * toward_zone >= 0. {
+            (lander.vx.abs() - max_vx).max(0.)
+        } else {
+            // flying away from the zone
+            lander.vx.abs()
+        };
+        let vy_excess = (-lander.vy - max_vy).max(0.);
+
+        self.settings.distance_weight * (dx + altitude + 10. * obstacle_depth) / 1000.
+            + self.settings.speed_weight * ((vx_excess + vy_excess) / 10.).powi(2)
+            + self.settings.angle_weight * lander.angle.abs() / 90.
+    }
+
+    /// Cost of getting too close to the terrain on the way
+    fn trajectory_cost(&self, history: &LanderHistory) -> f64 {
+        let terrain = self.model.terrain();
+        history
+            .iter_history()
+            .map(|lander| {
+                let height =
+                    lander.y - terrain.ground_below(lander.x, lander.y).unwrap_or(lander.y);
+                (1. - height / 100.).max(0.)
+            })
+            .sum::<f64>()
+            / self.settings.horizon as f64
+    }
+
+    /// Plan with commands from a random step on replaced by a random one
+    fn perturbed(plan: &Chromosome, rng: &mut impl Rng) -> Chromosome {
+        let mut plan = plan.clone();
+        let from = rng.gen_range(0..plan.angles.len());
+        let angle = if rng.gen_bool(0.5) {
+            rng.gen_range(-90..=90)
+        } else {
+            clamp(plan.angles[from] + rng.gen_range(-15..=15), -90..=90)
+        };
+        let thrust = rng.gen_range(0..=4);
+        plan.angles[from..].fill(angle);
+        plan.thrusts[from..].fill(thrust);
+        plan
+    }
+}
+
+impl Controller for MpcController {
+    fn command(&mut self, _: usize, lander: &LanderState, _: &Terrain) -> Option<Command> {
+        if self.settings.horizon == 0 {
+            return None;
+        }
+        // start by holding the current command, later shift the previous plan
+        if self.plan.angles.is_empty() {
+            self.plan = Chromosome {
+                angles: vec![lander.angle.round() as i32; self.settings.horizon],
+                thrusts: vec![lander.power; self.settings.horizon],
+            };
+        } else {
+            let last = (*self.plan.angles.last()?, *self.plan.thrusts.last()?);
+            self.plan.angles.rotate_left(1);
+            self.plan.thrusts.rotate_left(1);
+            *self.plan.angles.last_mut()? = last.0;
+            *self.plan.thrusts.last_mut()? = last.1;
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut best = self.predict(lander, &self.plan)?;
+        for _ in 0..self.settings.iterations {
+            let candidate = Self::perturbed(&self.plan, &mut rng);
+            let prediction = self.predict(lander, &candidate)?;
+            if prediction.improves_on(&best) {
+                best = prediction;
+                self.plan = candidate;
+            }
+        }
+        self.plan.get_cmd(0)
+    }
+}
+
+/// Outcome of a plan predicted over the horizon
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Prediction {
+    /// Cost of a flight still in progress or landed correctly, the lower the better
+    Flight(f64),
+    /// Error of the crash, the lower the better
+    Crash(f64),
+}
+
+impl Prediction {
+    /// Crashes never improve on a flight whatever its cost, they are only compared between
+    /// themselves while no plan avoids a crash
+    fn improves_on(&self, best: &Self) -> bool {
+        match (self, best) {
+            (Self::Flight(cost), Self::Flight(best)) => cost < best,
+            (Self::Flight(_), Self::Crash(_)) => true,
+            (Self::Crash(_), Self::Flight(_)) => false,
+            (Self::Crash(error), Self::Crash(best)) => error < best,
+        }
+    }
+}
+
+fn crash_error(landing: &Landing) -> f64 {
+    match landing {
+        Landing::Correct => 0.,
+        Landing::WrongTerrain { dist } => 10. + dist / 100.,
+        Landing::NotVertical { error_abs }
+        | Landing::TooFastVertical { error_abs }
+        | Landing::TooFastHorizontal { error_abs } => error_abs / 10.,
+    }
+}
+
+#[cfg(test)]
+mod mpc_test {
+    use super::*;
+
+    #[test]
+    fn crashes_rejected_whatever_the_cost() {
+        let (flight, crash) = (Prediction::Flight(1e9), Prediction::Crash(0.));
+        assert!(!crash.improves_on(&flight));
+        assert!(flight.improves_on(&crash));
+        assert!(Prediction::Crash(1.).improves_on(&Prediction::Crash(2.)));
+    }
+
+    #[test]
+    fn recovers_from_disturbance() {
+        let terrain = Terrain::with_default_limits(
+            vec![0., 2000., 3000., 6999.],
+            vec![100., 100., 300., 500.],
+        );
+        let mut mpc =
+            MpcController::try_with_default_model(&terrain, MpcSettings::default()).unwrap();
+        let runner = LanderRunner::new(
+            LanderState::default(),
+            1,
+            Physics::default(),
+            CollisionChecker::try_with_default_limits(terrain).unwrap(),
+        );
+        let lander = LanderState::default()
+            .with_x(3500.)
+            .with_y(2000.)
+            .with_fuel(2000);
+        let (_, history, _) = runner.fly(lander, 20, &mut mpc).unwrap();
+        let mut disturbed = history.last_state().unwrap();
+        disturbed.vx += 30.;
+        let (_, _, landing) = runner.fly(disturbed, 500, &mut mpc).unwrap();
+        assert!(matches!(landing, Some(Landing::Correct)), "{landing:?}");
+    }
+}
@@ -0,0 +1,383 @@
+use rand::Rng;
+
+use super::{
+    collision::defaults,
+    optimizer::standard_normal,
+    solver::{evolve, Genome},
+    CollisionChecker, Command, Controller, DefaultFitness, FitnessFunction, LanderHistory,
+    LanderRunner, LanderState, Landing, Physics, Terrain,
+};
+use crate::{ConfigError, CrossoverError, Error, SimulationError, SolverError, TerrainError};
+
+/// Standard deviation of the noise added to mutated weights
+const MUTATION_SIGMA: f64 = 0.3;
+/// Scales of the lander state network inputs
+const SPEED_SCALE: f64 = 100.;
+const FUEL_SCALE: f64 = 2000.;
+
+/// Feed-forward network with tanh activations, piloting the lander from its state and terrain
+/// features (see [`NeuralNetwork::features`]).
+///
+/// Outputs in range [-1,1] are scaled into absolute angle and power.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeuralNetwork {
+    layers: Vec<usize>,
+    weights: Vec<f64>,
+}
+
+impl NeuralNetwork {
+    pub const INPUTS: usize = 10;
+    pub const OUTPUTS: usize = 2;
+
+    /// Network with randomly initialized weights, scaled by the number of layer inputs
+    pub fn new_random(hidden_layers: &[usize]) -> Self {
+        let layers = Self::all_layers(hidden_layers);
+        let mut rng = rand::thread_rng();
+        let weights = layers
+            .windows(2)
+            .flat_map(|w| {
+                let scale = 1. / ((w[0] + 1) as f64).sqrt();
+                (0..(w[0] + 1) * w[1])
+                    .map(|_| standard_normal(&mut rng) * scale)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        Self { layers, weights }
+    }
+
+    /// `layers` are sizes of all layers including input and output ones, `weights` are laid out
+    /// layer by layer, each neuron's input weights followed by its bias
+    pub fn try_from_weights(layers: Vec<usize>, weights: Vec<f64>) -> Result<Self, ConfigError> {
+        let layer_mismatch = |key, expected: usize, actual: Option<&usize>| ConfigError::Mismatch {
+            key,
+            expected: expected as f64,
+            actual: actual.copied().unwrap_or_default() as f64,
+        };
+        if layers.first() != Some(&Self::INPUTS) {
+            return Err(layer_mismatch("Inputs", Self::INPUTS, layers.first()));
+        }
+        if layers.last() != Some(&Self::OUTPUTS) {
+            return Err(layer_mismatch("Outputs", Self::OUTPUTS, layers.last()));
+        }
+        let expected = Self::weights_count(&layers);
+        if weights.len() != expected {
+            return Err(layer_mismatch("Weights", expected, Some(&weights.len())));
+        }
+        Ok(Self { layers, weights })
+    }
+
+    pub fn layers(&self) -> &[usize] {
+        &self.layers
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn forward(&self, inputs: &[f64]) -> Vec<f64> {
+        let mut weights = self.weights.iter();
+        self.layers.windows(2).fold(inputs.to_vec(), |values, w| {
+            (0..w[1])
+                .map(|_| {
+                    let sum = values
+                        .iter()
+                        .zip(weights.by_ref().take(w[0]))
+                        .map(|(v, w)| v * w)
+                        .sum::<f64>()
+                        + weights.next().unwrap_or(&0.);
+                    sum.tanh()
+                })
+                .collect()
+        })
+    }
+
+    /// Network inputs: lander state and its position relative to the landing zone and terrain,
+    /// roughly scaled into [-1,1]. `None` if the terrain has no landing zone.
+    pub fn features(lander: &LanderState, terrain: &Terrain) -> Option<[f64; Self::INPUTS]> {
+        let (left, right) = terrain.landing_zone()?;
+        let target_x = (left.x + right.x) / 2.;
+        Some([
+            (target_x - lander.x) / defaults::MAX_X,
+            (lander.y - left.y) / defaults::MAX_Y,
+            (right.x - left.x) / 2. / defaults::MAX_X,
+            lander.vx / SPEED_SCALE,
+            lander.vy / SPEED_SCALE,
+            lander.angle / 90.,
+            lander.power as f64 / 4.,
+            lander.fuel as f64 / FUEL_SCALE,
+            (lander.y - terrain.max_height_between(lander.x, lander.x)) / defaults::MAX_Y,
+            (lander.y - terrain.max_height_between(lander.x, target_x)) / defaults::MAX_Y,
+        ])
+    }
+
+    fn all_layers(hidden_layers: &[usize]) -> Vec<usize> {
+        std::iter::once(Self::INPUTS)
+            .chain(hidden_layers.iter().copied())
+            .chain(std::iter::once(Self::OUTPUTS))
+            .collect()
+    }
+
+    fn weights_count(layers: &[usize]) -> usize {
+        layers.windows(2).map(|w| (w[0] + 1) * w[1]).sum()
+    }
+}
+
+impl Controller for NeuralNetwork {
+    fn command(&mut self, _: usize, lander: &LanderState, terrain: &Terrain) -> Option<Command> {
+        let outputs = self.forward(&Self::features(lander, terrain)?);
+        Some(Command::new(
+            (outputs[0] * 90.).round(),
+            ((outputs[1] + 1.) * 2.).round() as i32,
+        ))
+    }
+}
+
+impl Genome for NeuralNetwork {
+    /// Arithmetic blend of the parents' weights
+    fn crossover(&self, other: &Self, cross_point: f64) -> Result<(Self, Self), SolverError> {
+        if self.layers != other.layers {
+            return Err(SolverError::Crossover {
+                genes: "weights",
+                source: CrossoverError::SizeMismatch {
+                    a: self.weights.len(),
+                    b: other.weights.len(),
+                },
+            });
+        }
+        let blend = |a: &[f64], b: &[f64]| {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| cross_point * a + (1. - cross_point) * b)
+                .collect()
+        };
+        Ok((
+            Self {
+                layers: self.layers.clone(),
+                weights: blend(&self.weights, &other.weights),
+            },
+            Self {
+                layers: self.layers.clone(),
+                weights: blend(&other.weights, &self.weights),
+            },
+        ))
+    }
+
+    /// Adds gaussian noise to each weight with `mutation_prob`
+    fn mutate(&mut self, mutation_prob: f64, rng: &mut impl Rng) {
+        for weight in &mut self.weights {
+            if rng.gen_range(0f64..1.) < mutation_prob {
+                *weight += standard_normal(rng) * MUTATION_SIGMA;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NeuroSettings {
+    pub population_size: usize,
+    /// Sizes of hidden layers
+    pub hidden_layers: Vec<usize>,
+    pub elitism: f64,
+    /// Probability of mutating a weight
+    pub mutation_prob: f64,
+}
+
+impl Default for NeuroSettings {
+    fn default() -> Self {
+        Self {
+            population_size: 100,
+            hidden_layers: vec![8],
+            elitism: 0.2,
+            mutation_prob: 0.1,
+        }
+    }
+}
+
+/// Summary of an evaluated generation
+#[derive(Clone, Debug)]
+pub struct NeuroGeneration {
+    pub best_fitness: f64,
+    /// Number of scenarios the best network landed correctly
+    pub correct_landings: usize,
+}
+
+/// Genetic algorithm evolving weights of [`NeuralNetwork`] landing policies.
+///
+/// Every network flies closed-loop in every scenario, its fitness is the mean of its
+/// (population relative) fitness over scenarios, so the evolved policy has to generalize.
+pub struct NeuroEvolution {
+    population: Vec<NeuralNetwork>,
+    scenarios: Vec<(LanderState, LanderRunner)>,
+    fitness_function: Box<dyn FitnessFunction>,
+    elitism: f64,
+    mutation_prob: f64,
+    best: Option<NeuralNetwork>,
+}
+
+impl NeuroEvolution {
+    pub fn try_new(
+        settings: NeuroSettings,
+        scenarios: Vec<(LanderState, Terrain)>,
+    ) -> Result<Self, Error> {
+        if scenarios.is_empty() {
+            return Err(ConfigError::OutOfRange {
+                key: "Scenarios",
+                value: 0.,
+                min: 1.,
+                max: f64::INFINITY,
+            }
+            .into());
+        }
+        ConfigError::check_probability("Elitism", settings.elitism)?;
+        ConfigError::check_probability("MutationProb", settings.mutation_prob)?;
+        let scenarios = scenarios
+            .into_iter()
+            .map(|(lander, terrain)| scenario_runner(lander, terrain))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            population: (0..settings.population_size)
+                .map(|_| NeuralNetwork::new_random(&settings.hidden_layers))
+                .collect(),
+            scenarios,
+            fitness_function: Box::new(DefaultFitness::default()),
+            elitism: settings.elitism,
+            mutation_prob: settings.mutation_prob,
+            best: None,
+        })
+    }
+
+    pub fn with_fitness_function(self, fitness_function: impl FitnessFunction + 'static) -> Self {
+        Self {
+            fitness_function: Box::new(fitness_function),
+            ..self
+        }
+    }
+
+    /// Evaluates the population in all scenarios and replaces it with the next generation
+    pub fn next_generation(&mut self) -> Result<NeuroGeneration, Error> {
+        let mut fitness = vec![0.; self.population.len()];
+        let mut correct_landings = vec![0; self.population.len()];
+        for (lander, runner) in &self.scenarios {
+            let results = self
+                .population
+                .iter()
+                .map(|network| fly(runner, lander, &mut network.clone()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let scenario_fitness = self
+                .fitness_function
+                .calculate(&results.iter().map(|(h, l)| (h, l)).collect::<Vec<_>>())
+                .ok_or(SolverError::Fitness)?;
+            for (id, ((_, landing), scenario_fitness)) in
+                results.iter().zip(scenario_fitness).enumerate()
+            {
+                fitness[id] += scenario_fitness / self.scenarios.len() as f64;
+                if let Landing::Correct = landing {
+                    correct_landings[id] += 1;
+                }
+            }
+        }
+        let (best_id, best_fitness) = fitness
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|(_, fitness1), (_, fitness2)| fitness1.total_cmp(fitness2))
+            .ok_or(SolverError::EmptyPopulation)?;
+        self.best = Some(self.population[best_id].clone());
+        self.population = evolve(
+            &self.population,
+            fitness.into_iter(),
+            self.elitism,
+            self.mutation_prob,
+            false,
+            &mut rand::thread_rng(),
+        )?;
+        Ok(NeuroGeneration {
+            best_fitness,
+            correct_landings: correct_landings[best_id],
+        })
+    }
+
+    /// Best network of the last evaluated generation
+    pub fn best(&self) -> Option<&NeuralNetwork> {
+        self.best.as_ref()
+    }
+}
+
+/// Flies `controller` from `lander` over `terrain` until it lands, e.g. to validate an evolved
+/// policy on unseen terrain
+pub fn fly_scenario(
+    controller: &mut impl Controller,
+    lander: LanderState,
+    terrain: Terrain,
+) -> Result<(LanderHistory, Landing), Error> {
+    let (lander, runner) = scenario_runner(lander, terrain)?;
+    Ok(fly(&runner, &lander, controller)?)
+}
+
+fn scenario_runner(
+    lander: LanderState,
+    terrain: Terrain,
+) -> Result<(LanderState, LanderRunner), TerrainError> {
+    let checker =
+        CollisionChecker::try_with_default_limits(terrain).ok_or(TerrainError::NoLandingZone)?;
+    let runner = LanderRunner::new(lander.clone(), 1, Physics::default(), checker);
+    Ok((lander, runner))
+}
+
+fn fly(
+    runner: &LanderRunner,
+    lander: &LanderState,
+    controller: &mut impl Controller,
+) -> Result<(LanderHistory, Landing), SimulationError> {
+    runner.simulate(lander.clone(), controller)
+}
+
+#[cfg(test)]
+mod neuro_test {
+    use super::*;
+
+    #[test]
+    fn weights_layout() {
+        let network = NeuralNetwork::new_random(&[4, 3]);
+        assert_eq!(network.layers(), [10, 4, 3, 2]);
+        assert_eq!(network.weights().len(), 11 * 4 + 5 * 3 + 4 * 2);
+        assert_eq!(network.forward(&[0.; 10]).len(), 2);
+        assert!(NeuralNetwork::try_from_weights(vec![10, 2], vec![0.; 22]).is_ok());
+        assert!(NeuralNetwork::try_from_weights(vec![10, 2], vec![0.; 21]).is_err());
+        assert!(NeuralNetwork::try_from_weights(vec![9, 2], vec![0.; 20]).is_err());
+    }
+
+    #[test]
+    fn forward_single_layer() {
+        let mut weights = vec![0.; 22];
+        weights[0] = 1.; // first input to first output
+        weights[21] = 0.5; // second output bias
+        let network = NeuralNetwork::try_from_weights(vec![10, 2], weights).unwrap();
+        let outputs = network.forward(&[0.3, 1., 1., 1., 1., 1., 1., 1., 1., 1.]);
+        assert!((outputs[0] - 0.3f64.tanh()).abs() < 1e-12);
+        assert!((outputs[1] - 0.5f64.tanh()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn evolution_runs() {
+        let terrain = Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]);
+        let lander = LanderState::default()
+            .with_x(500.)
+            .with_y(1000.)
+            .with_fuel(1000);
+        let mut evolution = NeuroEvolution::try_new(
+            NeuroSettings {
+                population_size: 21,
+                ..Default::default()
+            },
+            vec![(lander, terrain)],
+        )
+        .unwrap();
+        let fitness: Vec<_> = (0..5)
+            .map(|_| evolution.next_generation().unwrap().best_fitness)
+            .collect();
+        assert!(evolution.best().is_some());
+        assert_eq!(evolution.population.len(), 21);
+        assert!(fitness.iter().all(|f| f.is_finite()));
+    }
+}
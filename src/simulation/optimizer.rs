@@ -0,0 +1,191 @@
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::{ConfigError, SolverError};
+
+use super::{
+    solver::{clamp, ANGLE_STEP_RANGE, THRUST_STEP_RANGE},
+    Chromosome, CmaEs, DeltaEncoding, Encoding, EncodingKind, ParticleSwarm, RngState,
+    SimulatedAnnealing, Solver, SolverSettings,
+};
+
+/// Search strategy evolving a population of chromosomes with delta encoded genes
+pub trait Optimizer: Send {
+    fn iter_population(&self) -> Box<dyn Iterator<Item = &Chromosome> + '_>;
+
+    /// Population members can be replaced (e.g. by local search), changes are taken into account
+    /// by the next [`Optimizer::new_generation`]
+    fn population_mut(&mut self) -> &mut [Chromosome];
+
+    /// Decodes genes into absolute commands, ready to be simulated
+    fn decode(&self, chromosome: &Chromosome) -> Chromosome;
+
+    /// Encoding of the genes, its operators can be used to modify the population
+    fn encoding(&self) -> &dyn Encoding {
+        &DeltaEncoding
+    }
+
+    fn iter_accumulated_population(&self) -> Box<dyn Iterator<Item = Chromosome> + '_> {
+        Box::new(self.iter_population().map(|c| self.decode(c)))
+    }
+
+    /// Replaces the population using `fitness` of its members (the higher the better)
+    fn new_generation(&mut self, fitness: &[f64]) -> Result<(), SolverError>;
+
+    /// Whether [`Optimizer::new_generation`] compares fitness with the one of earlier
+    /// generations, it's then given
+    /// [`FitnessFunction::calculate_comparable`](super::FitnessFunction::calculate_comparable)
+    /// instead of the fitness normalized within the population
+    fn remembers_fitness(&self) -> bool {
+        false
+    }
+
+    /// State of the random generator driving the search, `None` if the optimizer has internal
+    /// state beyond its population and can't be checkpointed
+    fn rng_state(&self) -> Option<RngState> {
+        None
+    }
+
+    /// Restores the state taken by [`Optimizer::rng_state`], `false` if checkpoints aren't
+    /// supported
+    fn restore_rng(&mut self, _state: &RngState) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    #[default]
+    Genetic,
+    CmaEs,
+    ParticleSwarm,
+    SimulatedAnnealing,
+}
+
+impl FromStr for Algorithm {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Genetic" => Ok(Self::Genetic),
+            "CmaEs" => Ok(Self::CmaEs),
+            "ParticleSwarm" => Ok(Self::ParticleSwarm),
+            "SimulatedAnnealing" => Ok(Self::SimulatedAnnealing),
+            _ => Err(ConfigError::UnknownVariant {
+                key: "Algorithm",
+                value: s.to_string(),
+                expected: &["Genetic", "CmaEs", "ParticleSwarm", "SimulatedAnnealing"],
+            }),
+        }
+    }
+}
+
+impl Algorithm {
+    pub fn create_optimizer(
+        self,
+        settings: SolverSettings,
+    ) -> Result<Box<dyn Optimizer>, ConfigError> {
+        if self != Self::Genetic && settings.encoding != EncodingKind::Delta {
+            return Err(ConfigError::Unsupported {
+                feature: format!("Encoding {:?}", settings.encoding),
+                algorithm: self,
+            });
+        }
+        Ok(match self {
+            Self::Genetic => Box::new(Solver::try_new(settings)?),
+            Self::CmaEs => Box::new(CmaEs::try_new(settings)?),
+            Self::ParticleSwarm => Box::new(ParticleSwarm::try_new(settings)?),
+            Self::SimulatedAnnealing => Box::new(SimulatedAnnealing::try_new(settings)?),
+        })
+    }
+}
+
+impl Optimizer for Solver {
+    fn iter_population(&self) -> Box<dyn Iterator<Item = &Chromosome> + '_> {
+        Box::new(Solver::iter_population(self))
+    }
+
+    fn population_mut(&mut self) -> &mut [Chromosome] {
+        &mut self.population
+    }
+
+    fn decode(&self, chromosome: &Chromosome) -> Chromosome {
+        Solver::decode(self, chromosome)
+    }
+
+    fn encoding(&self) -> &dyn Encoding {
+        Solver::encoding(self)
+    }
+
+    fn new_generation(&mut self, fitness: &[f64]) -> Result<(), SolverError> {
+        Solver::new_generation(self, fitness.iter().copied())
+    }
+
+    fn rng_state(&self) -> Option<RngState> {
+        Some(Solver::rng_state(self))
+    }
+
+    fn restore_rng(&mut self, state: &RngState) -> bool {
+        Solver::restore_rng(self, state);
+        true
+    }
+}
+
+/// Continuous relaxation of a chromosome, see [`from_relaxed`]
+pub(super) fn relaxed(chromosome: &Chromosome) -> Vec<f64> {
+    let angle_scale = *ANGLE_STEP_RANGE.end() as f64;
+    let thrust_scale = *THRUST_STEP_RANGE.end() as f64;
+    chromosome
+        .angles
+        .iter()
+        .map(|a| *a as f64 / angle_scale)
+        .chain(chromosome.thrusts.iter().map(|t| *t as f64 / thrust_scale))
+        .collect()
+}
+
+/// Chromosome from its continuous relaxation: angle genes followed by thrust genes, each scaled so
+/// that [-1, 1] covers its step range. Genes are rounded and clamped into their step ranges.
+pub(super) fn from_relaxed(genes: &[f64]) -> Chromosome {
+    let angle_scale = *ANGLE_STEP_RANGE.end() as f64;
+    let thrust_scale = *THRUST_STEP_RANGE.end() as f64;
+    let (angles, thrusts) = genes.split_at(genes.len() / 2);
+    Chromosome {
+        angles: angles
+            .iter()
+            .map(|a| clamp((a * angle_scale).round() as i32, ANGLE_STEP_RANGE))
+            .collect(),
+        thrusts: thrusts
+            .iter()
+            .map(|t| clamp((t * thrust_scale).round() as i32, THRUST_STEP_RANGE))
+            .collect(),
+    }
+}
+
+/// Sample of the standard normal distribution (Box-Muller transform)
+pub(super) fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.);
+    let u2: f64 = rng.gen_range(0f64..1.);
+    (-2. * u1.ln()).sqrt() * (2. * std::f64::consts::PI * u2).cos()
+}
+
+#[cfg(test)]
+mod relaxation_test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let chromosome = Chromosome {
+            angles: vec![-15, 3, 0, 15],
+            thrusts: vec![1, 0, -1, 1],
+        };
+        assert_eq!(from_relaxed(&relaxed(&chromosome)), chromosome);
+    }
+
+    #[test]
+    fn clamped() {
+        let chromosome = from_relaxed(&[2., -0.5, 3., -0.4]);
+        assert_eq!(chromosome.angles, vec![15, -8]);
+        assert_eq!(chromosome.thrusts, vec![1, 0]);
+    }
+}
@@ -0,0 +1,233 @@
+use std::f64::consts::PI;
+
+use super::{collision::defaults, fuel_left, FitnessFunction, LanderHistory, Landing};
+
+/// Landing errors minimized separately by the multi-objective (NSGA-II style) fitness
+#[derive(Clone, Debug, PartialEq)]
+pub struct Objectives {
+    /// Distance from the landing zone measured along terrain, 0 if landed on it
+    pub landing_distance: f64,
+    /// Sum of touchdown speeds exceeding the limits
+    pub speed_error: f64,
+    /// Absolute touchdown angle
+    pub angle_error: f64,
+    pub fuel_used: f64,
+}
+
+impl Objectives {
+    fn values(&self) -> [f64; 4] {
+        [
+            self.landing_distance,
+            self.speed_error,
+            self.angle_error,
+            self.fuel_used,
+        ]
+    }
+
+    /// True if `self` is not worse in any objective and better in at least one
+    pub fn dominates(&self, other: &Self) -> bool {
+        let (a, b) = (self.values(), other.values());
+        a.iter().zip(&b).all(|(a, b)| a <= b) && a.iter().zip(&b).any(|(a, b)| a < b)
+    }
+}
+
+/// Ranks landers by Pareto front and crowding distance instead of a weighted error sum
+#[derive(Clone, Debug)]
+pub struct ParetoFitness {
+    max_vertical_speed: f64,
+    max_horizontal_speed: f64,
+}
+
+impl Default for ParetoFitness {
+    fn default() -> Self {
+        Self::new(defaults::MAX_VERTICAL_SPEED, defaults::MAX_HORIZONTAL_SPEED)
+    }
+}
+
+impl ParetoFitness {
+    pub fn new(max_vertical_speed: f64, max_horizontal_speed: f64) -> Self {
+        Self {
+            max_vertical_speed,
+            max_horizontal_speed,
+        }
+    }
+
+    pub fn objectives(&self, history: &LanderHistory, landing: &Landing) -> Option<Objectives> {
+        let initial = history.initial_state()?;
+        let last = history.last_state()?;
+        Some(Objectives {
+            landing_distance: match landing {
+                Landing::WrongTerrain { dist } => *dist,
+                _ => 0.,
+            },
+            speed_error: (last.vx.abs() - self.max_horizontal_speed).max(0.)
+                + (last.vy.abs() - self.max_vertical_speed).max(0.),
+            angle_error: last.angle.abs(),
+            fuel_used: (initial.fuel - last.fuel) as f64,
+        })
+    }
+}
+
+impl FitnessFunction for ParetoFitness {
+    /// Fitness is the number of fronts below the lander's one plus its crowding distance squeezed
+    /// into [0, 0.5], so sorting by fitness gives NSGA-II crowded-comparison order.
+    fn calculate(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        let objectives = results
+            .iter()
+            .map(|(history, landing)| self.objectives(history, landing))
+            .collect::<Option<Vec<_>>>()?;
+        let fronts = non_dominated_sort(&objectives);
+        let mut fitness = vec![0.; objectives.len()];
+        for (rank, front) in fronts.iter().enumerate() {
+            for (id, distance) in front.iter().zip(crowding_distance(&objectives, front)) {
+                fitness[*id] = (fronts.len() - rank) as f64 + distance.atan() / PI;
+            }
+        }
+        Some(fitness)
+    }
+
+    /// Correct landings score 1 plus the fraction of fuel left, crashes `1 / (1 + error)` of
+    /// the sum of their landing distance, speed and angle errors
+    fn calculate_comparable(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        results
+            .iter()
+            .map(|(history, landing)| {
+                let objectives = self.objectives(history, landing)?;
+                Some(match landing {
+                    Landing::Correct => 1. + fuel_left(history),
+                    _ => {
+                        1. / (1.
+                            + objectives.landing_distance
+                            + objectives.speed_error
+                            + objectives.angle_error)
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Splits solutions into Pareto fronts, the first one being non-dominated
+pub fn non_dominated_sort(objectives: &[Objectives]) -> Vec<Vec<usize>> {
+    let mut dominated_by_count = vec![0usize; objectives.len()];
+    let mut dominating: Vec<Vec<usize>> = vec![Vec::new(); objectives.len()];
+    for (i, a) in objectives.iter().enumerate() {
+        for (j, b) in objectives.iter().enumerate().skip(i + 1) {
+            if a.dominates(b) {
+                dominating[i].push(j);
+                dominated_by_count[j] += 1;
+            } else if b.dominates(a) {
+                dominating[j].push(i);
+                dominated_by_count[i] += 1;
+            }
+        }
+    }
+
+    let mut fronts = Vec::new();
+    let mut front: Vec<_> = (0..objectives.len())
+        .filter(|i| dominated_by_count[*i] == 0)
+        .collect();
+    while !front.is_empty() {
+        let mut next_front = Vec::new();
+        for i in &front {
+            for j in &dominating[*i] {
+                dominated_by_count[*j] -= 1;
+                if dominated_by_count[*j] == 0 {
+                    next_front.push(*j);
+                }
+            }
+        }
+        fronts.push(front);
+        front = next_front;
+    }
+    fronts
+}
+
+/// Crowding distance of every `front` member, boundary solutions get infinity
+pub fn crowding_distance(objectives: &[Objectives], front: &[usize]) -> Vec<f64> {
+    let mut distances = vec![0.; front.len()];
+    if front.len() <= 2 {
+        return vec![f64::INFINITY; front.len()];
+    }
+    for objective in 0..4 {
+        let value = |i: usize| objectives[front[i]].values()[objective];
+        let mut order: Vec<_> = (0..front.len()).collect();
+        order.sort_by(|a, b| value(*a).total_cmp(&value(*b)));
+        let (min, max) = (value(order[0]), value(order[order.len() - 1]));
+        if max == min {
+            continue;
+        }
+        distances[order[0]] = f64::INFINITY;
+        distances[order[order.len() - 1]] = f64::INFINITY;
+        for w in order.windows(3) {
+            distances[w[1]] += (value(w[2]) - value(w[0])) / (max - min);
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod pareto_test {
+    use super::*;
+
+    fn obj(landing_distance: f64, fuel_used: f64) -> Objectives {
+        Objectives {
+            landing_distance,
+            speed_error: 0.,
+            angle_error: 0.,
+            fuel_used,
+        }
+    }
+
+    #[test]
+    fn domination() {
+        assert!(obj(1., 1.).dominates(&obj(2., 1.)));
+        assert!(!obj(1., 1.).dominates(&obj(1., 1.)));
+        assert!(!obj(1., 2.).dominates(&obj(2., 1.)));
+    }
+
+    #[test]
+    fn sorting() {
+        let objectives = [
+            obj(1., 3.),
+            obj(3., 1.),
+            obj(2., 2.),
+            obj(3., 3.),
+            obj(4., 4.),
+        ];
+        let fronts = non_dominated_sort(&objectives);
+        assert_eq!(fronts, vec![vec![0, 1, 2], vec![3], vec![4]]);
+    }
+
+    #[test]
+    fn crowding() {
+        let objectives = [obj(1., 4.), obj(2., 3.), obj(4., 1.), obj(3.9, 1.1)];
+        let distances = crowding_distance(&objectives, &[0, 1, 2, 3]);
+        assert!(distances[0].is_infinite());
+        assert!(distances[2].is_infinite());
+        assert!(distances[1] > distances[3]);
+    }
+
+    #[test]
+    fn comparable_fitness_independent_of_population() {
+        let history = LanderHistory::with_initial_state(crate::LanderState::default());
+        let (near, far) = (
+            Landing::WrongTerrain { dist: 10. },
+            Landing::WrongTerrain { dist: 100. },
+        );
+        let fitness = ParetoFitness::default();
+        let alone = |landing| {
+            fitness
+                .calculate_comparable(&[(&history, landing)])
+                .unwrap()[0]
+        };
+        assert!(alone(&Landing::Correct) > alone(&near));
+        assert!(alone(&near) > alone(&far));
+        assert_eq!(
+            fitness
+                .calculate_comparable(&[(&history, &far), (&history, &near)])
+                .unwrap(),
+            vec![alone(&far), alone(&near)]
+        );
+    }
+}
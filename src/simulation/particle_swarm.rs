@@ -0,0 +1,201 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+
+use super::{
+    optimizer::{from_relaxed, relaxed},
+    solver::{Angle, Thrust},
+    Chromosome, Optimizer, SolverSettings,
+};
+use crate::{ConfigError, SolverError};
+
+const INERTIA: f64 = 0.7;
+const COGNITIVE_WEIGHT: f64 = 1.5;
+const SOCIAL_WEIGHT: f64 = 1.5;
+const MAX_VELOCITY: f64 = 0.5;
+const INITIAL_MAX_VELOCITY: f64 = 0.1;
+
+struct Particle {
+    position: Vec<f64>,
+    velocity: Vec<f64>,
+    best: Option<(Vec<f64>, f64)>,
+}
+
+/// Particle swarm optimization over the continuous relaxation of chromosome genes
+/// (see `optimizer::from_relaxed`), each particle being one member of the population.
+///
+/// Best positions are remembered with the fitness they had when evaluated, so it's given fitness
+/// comparable between generations, see [`Optimizer::remembers_fitness`].
+pub struct ParticleSwarm {
+    particles: Vec<Particle>,
+    population: Vec<Chromosome>,
+    global_best: Option<(Vec<f64>, f64)>,
+    initial_angle: Angle,
+    initial_thrust: Thrust,
+    /// Drives the initial swarm and its moves, seeded by [`SolverSettings::seed`]
+    rng: ChaCha12Rng,
+}
+
+impl ParticleSwarm {
+    pub fn try_new(settings: SolverSettings) -> Result<Self, ConfigError> {
+        if settings.chromosome_size == 0 {
+            return Err(ConfigError::OutOfRange {
+                key: "ChromosomeSize",
+                value: 0.,
+                min: 1.,
+                max: f64::INFINITY,
+            });
+        }
+        let dim = 2 * settings.chromosome_size;
+        let mut rng = settings
+            .seed
+            .map_or_else(ChaCha12Rng::from_entropy, ChaCha12Rng::seed_from_u64);
+        let particles: Vec<_> = (0..settings.population_size)
+            .map(|_| Particle {
+                position: (0..dim).map(|_| rng.gen_range(-1f64..=1.)).collect(),
+                velocity: (0..dim)
+                    .map(|_| rng.gen_range(-INITIAL_MAX_VELOCITY..=INITIAL_MAX_VELOCITY))
+                    .collect(),
+                best: None,
+            })
+            .collect();
+        Ok(Self {
+            population: particles
+                .iter()
+                .map(|p| from_relaxed(&p.position))
+                .collect(),
+            particles,
+            global_best: None,
+            initial_angle: settings.initial_angle,
+            initial_thrust: settings.initial_thrust,
+            rng,
+        })
+    }
+
+    /// Moves particles whose chromosome was replaced to the replacement's position
+    fn sync_positions(&mut self) {
+        for (particle, chromosome) in self.particles.iter_mut().zip(&self.population) {
+            if from_relaxed(&particle.position) != *chromosome {
+                particle.position = relaxed(chromosome);
+            }
+        }
+    }
+
+    fn update_bests(&mut self, fitness: &[f64]) {
+        for (particle, fitness) in self.particles.iter_mut().zip(fitness) {
+            if particle
+                .best
+                .as_ref()
+                .is_none_or(|(_, best_fitness)| fitness > best_fitness)
+            {
+                particle.best = Some((particle.position.clone(), *fitness));
+            }
+            if self
+                .global_best
+                .as_ref()
+                .is_none_or(|(_, best_fitness)| fitness > best_fitness)
+            {
+                self.global_best = Some((particle.position.clone(), *fitness));
+            }
+        }
+    }
+
+    fn move_particles(&mut self) {
+        let rng = &mut self.rng;
+        let Some((global_best, _)) = &self.global_best else {
+            return;
+        };
+        for particle in &mut self.particles {
+            let Some((best, _)) = &particle.best else {
+                continue;
+            };
+            for (((x, v), best), global_best) in particle
+                .position
+                .iter_mut()
+                .zip(particle.velocity.iter_mut())
+                .zip(best)
+                .zip(global_best)
+            {
+                *v = (INERTIA * *v
+                    + COGNITIVE_WEIGHT * rng.gen_range(0f64..1.) * (best - *x)
+                    + SOCIAL_WEIGHT * rng.gen_range(0f64..1.) * (global_best - *x))
+                    .clamp(-MAX_VELOCITY, MAX_VELOCITY);
+                *x = (*x + *v).clamp(-1., 1.);
+            }
+        }
+    }
+}
+
+impl Optimizer for ParticleSwarm {
+    fn iter_population(&self) -> Box<dyn Iterator<Item = &Chromosome> + '_> {
+        Box::new(self.population.iter())
+    }
+
+    fn population_mut(&mut self) -> &mut [Chromosome] {
+        &mut self.population
+    }
+
+    fn decode(&self, chromosome: &Chromosome) -> Chromosome {
+        chromosome.accumulated(self.initial_angle, self.initial_thrust)
+    }
+
+    fn new_generation(&mut self, fitness: &[f64]) -> Result<(), SolverError> {
+        if fitness.len() != self.particles.len() {
+            return Err(SolverError::FitnessLength {
+                expected: self.particles.len(),
+                actual: fitness.len(),
+            });
+        }
+        self.sync_positions();
+        self.update_bests(fitness);
+        self.move_particles();
+        self.population = self
+            .particles
+            .iter()
+            .map(|p| from_relaxed(&p.position))
+            .collect();
+        Ok(())
+    }
+
+    fn remembers_fitness(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod particle_swarm_test {
+    use super::*;
+    use crate::simulation::EncodingKind;
+
+    #[test]
+    fn follows_best_particle() {
+        let mut swarm = ParticleSwarm::try_new(SolverSettings {
+            population_size: 10,
+            chromosome_size: 3,
+            initial_angle: 0,
+            initial_thrust: 0,
+            elitism: 0.,
+            mutation_prob: 0.,
+            unique_elites: false,
+            encoding: EncodingKind::Delta,
+            seed: None,
+        })
+        .unwrap();
+        let target = [0.4, -0.2, 0.1, 0.5, -1., 0.];
+        for _ in 0..200 {
+            let fitness: Vec<_> = swarm
+                .particles
+                .iter()
+                .map(|p| {
+                    -p.position
+                        .iter()
+                        .zip(target)
+                        .map(|(x, t)| (x - t).powi(2))
+                        .sum::<f64>()
+                })
+                .collect();
+            swarm.new_generation(&fitness).unwrap();
+        }
+        let (best, _) = swarm.global_best.unwrap();
+        assert!(best.iter().zip(target).all(|(x, t)| (x - t).abs() < 0.1));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{ConfigError, SimulationError};
+
 pub(crate) mod defaults {
     pub const G: f64 = 3.711;
     pub const ANGLE_STEP: f64 = 15.;
@@ -23,6 +25,14 @@ impl Command {
     pub fn new(angle: f64, power: i32) -> Self {
         Self { angle, power }
     }
+
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    pub fn power(&self) -> i32 {
+        self.power
+    }
 }
 
 impl Command {
@@ -82,9 +92,45 @@ impl LanderState {
     }
 }
 
-#[derive(Debug)]
-pub enum SimulationError {
-    InvalidThrust(Command),
+/// Physics constants a lander can be flown with, see [`AppBuilder`](crate::AppBuilder)
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsSettings {
+    /// Positive, without gravity a hovering lander would never land
+    pub gravity: f64,
+    /// Highest thrust, in range [1,4] as the solvers don't evolve stronger thrusts
+    pub power_max: i32,
+    /// Highest tilt in degrees either way, in range [0,90]
+    pub angle_limit: f64,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            gravity: defaults::G,
+            power_max: defaults::POWER_MAX,
+            angle_limit: defaults::ANGLE_LIMIT,
+        }
+    }
+}
+
+impl PhysicsSettings {
+    pub fn physics(&self) -> Physics {
+        Physics::default()
+            .with_g(self.gravity)
+            .with_power_max(self.power_max)
+            .with_angle_limit(self.angle_limit)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        ConfigError::check_range("Gravity", self.gravity, f64::MIN_POSITIVE, f64::INFINITY)?;
+        ConfigError::check_range(
+            "PowerMax",
+            self.power_max as f64,
+            1.,
+            defaults::POWER_MAX as f64,
+        )?;
+        ConfigError::check_range("AngleLimit", self.angle_limit, 0., defaults::ANGLE_LIMIT)
+    }
 }
 
 pub struct Physics {
@@ -178,6 +224,14 @@ impl Physics {
         Ok(lander)
     }
 
+    /// `cmd` clamped into the angle and power limits
+    pub fn limited(&self, cmd: Command) -> Command {
+        Command {
+            angle: cmd.angle.clamp(-self.angle_limit, self.angle_limit),
+            power: cmd.power.clamp(0, self.power_max),
+        }
+    }
+
     fn validate_thrust(&self, thrust: &Command) -> bool {
         thrust.angle.abs() <= self.angle_limit
             && thrust.power <= self.power_max
@@ -292,4 +346,31 @@ mod physics_tests {
             .unwrap();
         assert_eq!(iniitial_fuel - lander.fuel, 1);
     }
+
+    #[test]
+    fn limits() {
+        let settings = PhysicsSettings {
+            power_max: 3,
+            angle_limit: 45.,
+            ..PhysicsSettings::default()
+        };
+        assert_eq!(settings.validate(), Ok(()));
+        let physics = settings.physics();
+        let cmd = physics.limited(Command::new(-60., 4));
+        assert_eq!((cmd.angle(), cmd.power()), (-45., 3));
+        let lander = LanderState::default()
+            .with_y(500.)
+            .with_fuel(200)
+            .with_power(3);
+        assert!(physics
+            .iterate(lander.clone(), Command::new(0., 4))
+            .is_err());
+        assert_eq!(physics.iterate(lander, cmd).unwrap().power, 3);
+        assert!(PhysicsSettings {
+            power_max: 5,
+            ..settings
+        }
+        .validate()
+        .is_err());
+    }
 }
@@ -0,0 +1,469 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::ConfigError;
+
+use super::{
+    collision::check_collision,
+    distance,
+    physics::defaults::{G, POWER_MAX},
+    solver::clamp,
+    Autopilot, Command, Controller, FitnessFunction, LanderHistory, LanderState, Landing, Terrain,
+    Vec2,
+};
+
+/// Distance from a waypoint at which the lander heads to the next one
+const WAYPOINT_RADIUS: f64 = 150.;
+/// Maximal speed while flying between waypoints
+const CRUISE_SPEED: f64 = 40.;
+/// Deceleration assumed when approaching a waypoint
+const BRAKING: f64 = 1.;
+/// Maximal tilt in degrees while flying between waypoints
+const MAX_TILT: f64 = 45.;
+
+#[derive(Clone, Debug)]
+pub struct PlannerSettings {
+    /// Side of the grid cells searched for the corridor
+    pub cell_size: f64,
+    /// Distance kept between the corridor and the terrain
+    pub clearance: f64,
+}
+
+impl Default for PlannerSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 100.,
+            clearance: 150.,
+        }
+    }
+}
+
+impl PlannerSettings {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        ConfigError::check_range("CellSize", self.cell_size, f64::MIN_POSITIVE, f64::INFINITY)?;
+        ConfigError::check_range("Clearance", self.clearance, 0., f64::INFINITY)
+    }
+}
+
+/// Collision-free corridor from `start` to above the middle of the landing zone: waypoints to fly
+/// through in order, the last one `clearance` above the landing zone. `None` if the terrain has no
+/// landing zone or it can't be reached.
+///
+/// A* searches a grid of cells clear of the terrain, then the path is shortened to the waypoints
+/// seeing each other.
+pub fn plan_corridor(
+    terrain: &Terrain,
+    start: Vec2,
+    settings: &PlannerSettings,
+) -> Option<Vec<Vec2>> {
+    let (left, right) = terrain.landing_zone()?;
+    let goal = Vec2 {
+        x: (left.x + right.x) / 2.,
+        y: left.y + settings.clearance,
+    };
+    let grid = Grid::new(terrain, settings);
+    let start_cell = grid.nearest_visible_cell(terrain, start)?;
+    let goal_cell = grid.nearest_visible_cell(terrain, goal)?;
+    let cells = grid.shortest_path(start_cell, goal_cell)?;
+
+    let path: Vec<_> = std::iter::once(start)
+        .chain(cells.into_iter().map(|cell| grid.center(cell)))
+        .chain(std::iter::once(goal))
+        .collect();
+    let mut waypoints = Vec::new();
+    let mut current = 0;
+    while current < path.len() - 1 {
+        current = (current + 2..path.len())
+            .rev()
+            .find(|next| sees(terrain, path[current], path[*next], settings.clearance))
+            .unwrap_or(current + 1);
+        waypoints.push(path[current]);
+    }
+    Some(waypoints)
+}
+
+/// Distance from `point` to the closest point of the corridor it sees plus the length of the
+/// corridor left to fly from there. Points seeing no part of the corridor are as far as its start.
+pub fn corridor_distance(corridor: &[Vec2], terrain: &Terrain, point: Vec2) -> f64 {
+    let Some(first) = corridor.first() else {
+        return 0.;
+    };
+    let mut remaining: f64 = corridor
+        .windows(2)
+        .map(|leg| distance(leg[0], leg[1]))
+        .sum();
+    let mut best = (distance(*first, point), remaining);
+    for leg in corridor.windows(2) {
+        remaining -= distance(leg[0], leg[1]);
+        let closest = closest_point(leg[0], leg[1], point);
+        let offset = distance(closest, point);
+        if offset < best.0 && visible(terrain, point, closest) {
+            best = (offset, distance(closest, leg[1]) + remaining);
+        }
+    }
+    best.0 + best.1
+}
+
+/// Flies through the waypoints of a corridor, leaving the last leg to the [`Autopilot`]
+#[derive(Clone, Debug)]
+pub struct WaypointPilot {
+    waypoints: Vec<Vec2>,
+    next: usize,
+    autopilot: Autopilot,
+}
+
+impl WaypointPilot {
+    pub fn new(waypoints: Vec<Vec2>) -> Self {
+        Self {
+            waypoints,
+            next: 0,
+            autopilot: Autopilot::default(),
+        }
+    }
+}
+
+impl Controller for WaypointPilot {
+    fn command(&mut self, step: usize, lander: &LanderState, terrain: &Terrain) -> Option<Command> {
+        let position = Vec2 {
+            x: lander.x,
+            y: lander.y,
+        };
+        while self.next + 1 < self.waypoints.len()
+            && distance(position, self.waypoints[self.next]) < WAYPOINT_RADIUS
+        {
+            self.next += 1;
+        }
+        if self.next + 1 >= self.waypoints.len() {
+            return Controller::command(&mut self.autopilot, step, lander, terrain);
+        }
+        let target = self.waypoints[self.next];
+        let dist = distance(position, target);
+        let speed = CRUISE_SPEED.min((2. * BRAKING * dist).sqrt());
+        let desired_vx = (target.x - lander.x) / dist * speed;
+        let desired_vy = (target.y - lander.y) / dist * speed;
+        // thrust accelerating to the desired velocity in two steps against gravity, the vertical
+        // part first as the engine barely outweighs gravity
+        let max_power = POWER_MAX as f64;
+        let thrust_y = ((desired_vy - lander.vy) / 2. + G).clamp(0., max_power);
+        let max_thrust_x = (max_power.powi(2) - thrust_y.powi(2)).sqrt();
+        let thrust_x = ((desired_vx - lander.vx) / 2.).clamp(-max_thrust_x, max_thrust_x);
+        let angle = (-thrust_x).atan2(thrust_y).to_degrees();
+        let power = thrust_x.hypot(thrust_y).round() as i32;
+        Some(Command::new(
+            angle.clamp(-MAX_TILT, MAX_TILT).round(),
+            clamp(power, 0..=POWER_MAX),
+        ))
+    }
+}
+
+/// Fitness ranking landers crashed on the wrong terrain by [`corridor_distance`] of their last
+/// position before the crash rather than by the distance along the terrain, which is misleading
+/// in caves
+pub struct CorridorFitness {
+    inner: Box<dyn FitnessFunction>,
+    corridor: Vec<Vec2>,
+    terrain: Terrain,
+}
+
+impl CorridorFitness {
+    pub fn new(inner: Box<dyn FitnessFunction>, corridor: Vec<Vec2>, terrain: Terrain) -> Self {
+        Self {
+            inner,
+            corridor,
+            terrain,
+        }
+    }
+
+    /// Calls `fitness` with the wrong terrain landings remapped to the corridor distance
+    fn remapped(
+        &self,
+        results: &[(&LanderHistory, &Landing)],
+        fitness: impl FnOnce(&[(&LanderHistory, &Landing)]) -> Option<Vec<f64>>,
+    ) -> Option<Vec<f64>> {
+        let landings: Vec<_> = results
+            .iter()
+            .map(|(history, landing)| match landing {
+                Landing::WrongTerrain { .. } => {
+                    let steps = history.iter_history().count();
+                    let last = history.iter_history().nth(steps.saturating_sub(2))?;
+                    Some(Landing::WrongTerrain {
+                        dist: corridor_distance(
+                            &self.corridor,
+                            &self.terrain,
+                            Vec2 {
+                                x: last.x,
+                                y: last.y,
+                            },
+                        ),
+                    })
+                }
+                _ => Some((*landing).clone()),
+            })
+            .collect::<Option<_>>()?;
+        let results: Vec<_> = results
+            .iter()
+            .zip(&landings)
+            .map(|((history, _), landing)| (*history, landing))
+            .collect();
+        fitness(&results)
+    }
+}
+
+impl FitnessFunction for CorridorFitness {
+    fn calculate(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        self.remapped(results, |results| self.inner.calculate(results))
+    }
+
+    fn calculate_comparable(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        self.remapped(results, |results| self.inner.calculate_comparable(results))
+    }
+}
+
+/// Cells of the map, free ones far enough from the terrain
+struct Grid {
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    free: Vec<bool>,
+}
+
+impl Grid {
+    fn new(terrain: &Terrain, settings: &PlannerSettings) -> Self {
+        let columns = (terrain.max_x() / settings.cell_size).ceil() as usize;
+        let rows = (terrain.max_y() / settings.cell_size).ceil() as usize;
+        let mut grid = Self {
+            cell_size: settings.cell_size,
+            columns,
+            rows,
+            free: Vec::with_capacity(columns * rows),
+        };
+        grid.free = (0..columns * rows)
+            .map(|cell| {
+                let center = grid.center(cell);
+                in_air(terrain, center) && terrain_distance(terrain, center) >= settings.clearance
+            })
+            .collect();
+        grid
+    }
+
+    fn center(&self, cell: usize) -> Vec2 {
+        Vec2 {
+            x: ((cell % self.columns) as f64 + 0.5) * self.cell_size,
+            y: ((cell / self.columns) as f64 + 0.5) * self.cell_size,
+        }
+    }
+
+    fn neighbours(&self, cell: usize) -> impl Iterator<Item = usize> + '_ {
+        let (column, row) = (
+            (cell % self.columns) as isize,
+            (cell / self.columns) as isize,
+        );
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (column + dx, row + dy)))
+            .filter(move |(x, y)| {
+                (*x, *y) != (column, row)
+                    && (0..self.columns as isize).contains(x)
+                    && (0..self.rows as isize).contains(y)
+            })
+            .map(|(x, y)| y as usize * self.columns + x as usize)
+            .filter(|cell| self.free[*cell])
+    }
+
+    /// Closest free cell reachable from `point` in a straight line
+    fn nearest_visible_cell(&self, terrain: &Terrain, point: Vec2) -> Option<usize> {
+        (0..self.free.len())
+            .filter(|cell| self.free[*cell])
+            .map(|cell| (cell, distance(self.center(cell), point)))
+            .filter(|(cell, _)| sees(terrain, point, self.center(*cell), 0.))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(cell, _)| cell)
+    }
+
+    /// A* through free cells
+    fn shortest_path(&self, start: usize, goal: usize) -> Option<Vec<usize>> {
+        let heuristic = |cell| distance(self.center(cell), self.center(goal));
+        let mut cost = vec![f64::INFINITY; self.free.len()];
+        let mut previous = vec![None; self.free.len()];
+        let mut open = BinaryHeap::new();
+        cost[start] = 0.;
+        open.push(Node {
+            estimate: heuristic(start),
+            cell: start,
+        });
+        while let Some(Node { cell, .. }) = open.pop() {
+            if cell == goal {
+                let mut path = vec![goal];
+                while let Some(cell) = previous[*path.last()?] {
+                    path.push(cell);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbour in self.neighbours(cell) {
+                let new_cost = cost[cell] + distance(self.center(cell), self.center(neighbour));
+                if new_cost < cost[neighbour] {
+                    cost[neighbour] = new_cost;
+                    previous[neighbour] = Some(cell);
+                    open.push(Node {
+                        estimate: new_cost + heuristic(neighbour),
+                        cell: neighbour,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A* open set entry, the lowest estimate first
+struct Node {
+    estimate: f64,
+    cell: usize,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+/// Whether the terrain leaves `clearance` around the straight line from `a` to `b`, or as much
+/// as there is around its ends if that's less
+fn sees(terrain: &Terrain, a: Vec2, b: Vec2, clearance: f64) -> bool {
+    let clearance = clearance
+        .min(terrain_distance(terrain, a))
+        .min(terrain_distance(terrain, b));
+    visible(terrain, a, b)
+        && terrain.iter_segments().all(|(p, q)| {
+            [
+                point_segment_distance(a, p, q),
+                point_segment_distance(b, p, q),
+                point_segment_distance(p, a, b),
+                point_segment_distance(q, a, b),
+            ]
+            .into_iter()
+            .all(|d| d >= clearance)
+        })
+}
+
+/// Whether the straight line from `a` to `b` doesn't cross the terrain
+fn visible(terrain: &Terrain, a: Vec2, b: Vec2) -> bool {
+    a == b
+        || terrain
+            .iter_segments()
+            .all(|segment| check_collision((a, b), segment).is_none())
+}
+
+/// Whether `point` is above the terrain, i.e. a ray going up from it crosses the terrain an even
+/// number of times
+fn in_air(terrain: &Terrain, point: Vec2) -> bool {
+    terrain
+        .iter_points()
+        .zip(terrain.iter_points().skip(1))
+        .filter(|(a, b)| {
+            a.x.min(b.x) <= point.x
+                && point.x < a.x.max(b.x)
+                && a.y + (b.y - a.y) * (point.x - a.x) / (b.x - a.x) > point.y
+        })
+        .count()
+        % 2
+        == 0
+}
+
+/// Distance to the terrain and map boundaries
+fn terrain_distance(terrain: &Terrain, point: Vec2) -> f64 {
+    terrain
+        .iter_segments()
+        .map(|(a, b)| point_segment_distance(point, a, b))
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn closest_point(a: Vec2, b: Vec2, point: Vec2) -> Vec2 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0. {
+        0.
+    } else {
+        (((point.x - a.x) * dx + (point.y - a.y) * dy) / length_squared).clamp(0., 1.)
+    };
+    Vec2 {
+        x: a.x + t * dx,
+        y: a.y + t * dy,
+    }
+}
+
+fn point_segment_distance(point: Vec2, a: Vec2, b: Vec2) -> f64 {
+    distance(point, closest_point(a, b, point))
+}
+
+#[cfg(test)]
+mod planner_test {
+    use super::*;
+
+    /// Landing zone under an overhang, the cave entered from the left
+    fn cave() -> Terrain {
+        Terrain::with_default_limits(
+            vec![0., 1000., 2000., 3000., 3000., 1000., 1200., 6999.],
+            vec![1500., 200., 200., 600., 1200., 1000., 2000., 2200.],
+        )
+    }
+
+    #[test]
+    fn corridor_enters_cave() {
+        let terrain = cave();
+        let start = Vec2 { x: 5000., y: 2700. };
+        let corridor = plan_corridor(&terrain, start, &PlannerSettings::default()).unwrap();
+        let goal = *corridor.last().unwrap();
+        assert_eq!((goal.x, goal.y), (1500., 350.));
+        assert!(corridor.iter().any(|waypoint| waypoint.x < 1000.));
+        std::iter::once(start)
+            .chain(corridor.iter().copied())
+            .collect::<Vec<_>>()
+            .windows(2)
+            .for_each(|leg| assert!(sees(&terrain, leg[0], leg[1], 0.), "{leg:?}"));
+    }
+
+    #[test]
+    fn inside_rock() {
+        let terrain = cave();
+        assert!(in_air(&terrain, Vec2 { x: 1500., y: 600. }));
+        assert!(!in_air(&terrain, Vec2 { x: 1500., y: 1500. }));
+        assert!(in_air(&terrain, Vec2 { x: 500., y: 2500. }));
+        assert!(!in_air(&terrain, Vec2 { x: 1500., y: 100. }));
+    }
+
+    #[test]
+    fn remaining_corridor() {
+        let corridor = [
+            Vec2 { x: 0., y: 0. },
+            Vec2 { x: 100., y: 0. },
+            Vec2 { x: 100., y: 100. },
+        ];
+        let open = Terrain::with_default_limits(vec![0., 6999.], vec![-500., -500.]);
+        let distance = |point| corridor_distance(&corridor, &open, point);
+        assert_eq!(distance(Vec2 { x: 50., y: 10. }), 160.);
+        assert_eq!(distance(Vec2 { x: 100., y: 100. }), 0.);
+        // a wall hides the closer second leg
+        let wall = Terrain::with_default_limits(
+            vec![0., 88., 88., 92., 92., 6999.],
+            vec![-500., -500., 70., 70., -500., -500.],
+        );
+        assert_eq!(
+            corridor_distance(&corridor, &wall, Vec2 { x: 80., y: 50. }),
+            170.
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use super::{diversity::std_dev, LanderHistory, LanderState, Landing, LandingCriteria};
+
+/// Number of landers of a generation with each landing outcome
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LandingHistogram {
+    pub correct: usize,
+    pub wrong_terrain: usize,
+    pub not_vertical: usize,
+    pub too_fast_vertical: usize,
+    pub too_fast_horizontal: usize,
+}
+
+impl LandingHistogram {
+    pub fn record(&mut self, landing: &Landing) {
+        let count = match landing {
+            Landing::Correct => &mut self.correct,
+            Landing::WrongTerrain { .. } => &mut self.wrong_terrain,
+            Landing::NotVertical { .. } => &mut self.not_vertical,
+            Landing::TooFastVertical { .. } => &mut self.too_fast_vertical,
+            Landing::TooFastHorizontal { .. } => &mut self.too_fast_horizontal,
+        };
+        *count += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        self.correct
+            + self.wrong_terrain
+            + self.not_vertical
+            + self.too_fast_vertical
+            + self.too_fast_horizontal
+    }
+}
+
+/// Touchdown of a lander measured against the landing criteria
+#[derive(Clone, Debug, PartialEq)]
+pub struct TouchdownMargins {
+    /// Highest vertical speed less the touchdown one, negative when too fast
+    pub vertical_speed: f64,
+    /// Highest horizontal speed less the touchdown one, negative when too fast
+    pub horizontal_speed: f64,
+    /// Tilt in degrees, a correct landing needs 0
+    pub tilt: f64,
+    pub fuel: i32,
+}
+
+impl TouchdownMargins {
+    pub fn measure(touchdown: &LanderState, criteria: &LandingCriteria) -> Self {
+        Self {
+            vertical_speed: criteria.max_vertical_speed - touchdown.vy.abs(),
+            horizontal_speed: criteria.max_horizontal_speed - touchdown.vx.abs(),
+            tilt: touchdown.angle.abs(),
+            fuel: touchdown.fuel,
+        }
+    }
+}
+
+/// Statistics of a generation, produced by [`App::next_population`](crate::App::next_population)
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenerationReport {
+    pub generation: usize,
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+    pub median_fitness: f64,
+    pub std_fitness: f64,
+    pub landings: LandingHistogram,
+    /// Touchdown of the lander with the best fitness
+    pub best_touchdown: Option<TouchdownMargins>,
+    /// Time spent simulating the generation in [`App::run`](crate::App::run)
+    pub simulation_time: Duration,
+    /// Time spent ranking and evolving the generation, including local search
+    pub solver_time: Duration,
+}
+
+impl GenerationReport {
+    /// Report of the landers flown in `flights` with `fitness`, times are left zero
+    pub fn measure(
+        generation: usize,
+        fitness: &[f64],
+        flights: &[(&LanderHistory, &Landing)],
+        criteria: &LandingCriteria,
+    ) -> Self {
+        let mut sorted = fitness.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let mut landings = LandingHistogram::default();
+        flights
+            .iter()
+            .for_each(|(_, landing)| landings.record(landing));
+        let best_touchdown = (0..fitness.len())
+            .max_by(|a, b| fitness[*a].total_cmp(&fitness[*b]))
+            .and_then(|best| flights.get(best)?.0.last_state())
+            .map(|touchdown| TouchdownMargins::measure(&touchdown, criteria));
+        Self {
+            generation,
+            best_fitness: sorted.last().copied().unwrap_or(f64::NAN),
+            mean_fitness: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            median_fitness: median(&sorted),
+            std_fitness: std_dev(&sorted),
+            landings,
+            best_touchdown,
+            simulation_time: Duration::ZERO,
+            solver_time: Duration::ZERO,
+        }
+    }
+}
+
+/// Median of `sorted` values, NaN if there are none
+fn median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    match n {
+        0 => f64::NAN,
+        _ if n % 2 == 1 => sorted[n / 2],
+        _ => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.,
+    }
+}
+
+#[cfg(test)]
+mod report_test {
+    use super::*;
+
+    #[test]
+    fn statistics_of_generation() {
+        let history = |vx: f64, vy: f64| {
+            let mut history = LanderHistory::with_initial_state(LanderState::default());
+            history.append_lander_state(&LanderState::default().with_vx(vx).with_vy(vy));
+            history
+        };
+        let (slow, fast) = (history(-5., -30.), history(25., -50.));
+        let too_fast = Landing::TooFastVertical { error_abs: 10. };
+        let report = GenerationReport::measure(
+            3,
+            &[1., 4., 2., 5.],
+            &[
+                (&fast, &too_fast),
+                (&fast, &too_fast),
+                (&fast, &Landing::WrongTerrain { dist: 100. }),
+                (&slow, &Landing::Correct),
+            ],
+            &LandingCriteria::default(),
+        );
+        assert_eq!(report.best_fitness, 5.);
+        assert_eq!(report.mean_fitness, 3.);
+        assert_eq!(report.median_fitness, 3.);
+        assert!((report.std_fitness - 2.5f64.sqrt()).abs() < 1e-9);
+        assert_eq!(
+            report.landings,
+            LandingHistogram {
+                correct: 1,
+                wrong_terrain: 1,
+                too_fast_vertical: 2,
+                ..Default::default()
+            }
+        );
+        let best = report.best_touchdown.unwrap();
+        assert_eq!((best.vertical_speed, best.horizontal_speed), (10., 15.));
+    }
+}
@@ -1,14 +1,22 @@
 use std::ops::RangeInclusive;
 
-use rand::{seq::SliceRandom, Rng};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
 
-type Angle = i32;
-type Thrust = i32;
+use crate::{ConfigError, CrossoverError, SolverError};
 
-const ANGLE_RANGE: RangeInclusive<Angle> = -90..=90;
-const THRUST_RANGE: RangeInclusive<Thrust> = 0..=4;
-const ANGLE_STEP_RANGE: RangeInclusive<Angle> = -15..=15;
-const THRUST_STEP_RANGE: RangeInclusive<Thrust> = -1..=1;
+use super::{
+    AdaptiveHorizonSettings, Algorithm, Encoding, EncodingKind, FinalApproachSettings,
+    LocalSearchSettings, PlannerSettings, RngState, StagnationSettings,
+};
+
+pub(super) type Angle = i32;
+pub(super) type Thrust = i32;
+
+pub(super) const ANGLE_RANGE: RangeInclusive<Angle> = -90..=90;
+pub(super) const THRUST_RANGE: RangeInclusive<Thrust> = 0..=4;
+pub(super) const ANGLE_STEP_RANGE: RangeInclusive<Angle> = -15..=15;
+pub(super) const THRUST_STEP_RANGE: RangeInclusive<Thrust> = -1..=1;
 
 macro_rules! clamp {
     ($range:ident) => {
@@ -19,11 +27,88 @@ macro_rules! clamp {
 type AngleGenes = Vec<Angle>;
 type ThrustGenes = Vec<Thrust>;
 
+#[derive(Clone, Debug)]
 pub struct Settings {
     pub population_size: usize,
     pub chromosome_size: usize,
     pub elitism: f64,
     pub mutation_prob: f64,
+    /// Keep a single copy of identical chromosomes among the elites
+    pub unique_elites: bool,
+    /// Rank landers with Pareto fronts (NSGA-II style) instead of a single weighted score
+    pub multi_objective: bool,
+    pub algorithm: Algorithm,
+    pub encoding: EncodingKind,
+    /// Refine the best chromosomes with local search after each generation
+    pub local_search: Option<LocalSearchSettings>,
+    /// Fraction of the initial population seeded with the autopilot's flight and its perturbed
+    /// copies
+    pub heuristic_seed: f64,
+    /// Level the lander and slow its descent just before touchdown, overriding the solver's
+    /// commands. Off by default, landers about to land tilted are then not rescued
+    pub final_approach: Option<FinalApproachSettings>,
+    /// Restart the population when the search stagnates
+    pub stagnation: Option<StagnationSettings>,
+    /// Grow and shrink the chromosome to the length of the flights, genetic algorithm only
+    pub adaptive_horizon: Option<AdaptiveHorizonSettings>,
+    /// Plan a corridor of waypoints to the landing zone, the autopilot's seed flies through it and
+    /// landers crashing on the wrong terrain are ranked by the corridor left to fly
+    pub planner: Option<PlannerSettings>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            population_size: 200,
+            chromosome_size: 160,
+            elitism: 0.15,
+            mutation_prob: 0.01,
+            unique_elites: false,
+            multi_objective: false,
+            algorithm: Algorithm::default(),
+            encoding: EncodingKind::default(),
+            local_search: None,
+            heuristic_seed: 0.,
+            final_approach: None,
+            stagnation: None,
+            adaptive_horizon: None,
+            planner: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Checks every setting is in its range, also the optional ones which are set
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        ConfigError::check_range(
+            "PopulationSize",
+            self.population_size as f64,
+            1.,
+            f64::INFINITY,
+        )?;
+        ConfigError::check_range(
+            "ChromosomeSize",
+            self.chromosome_size as f64,
+            1.,
+            f64::INFINITY,
+        )?;
+        ConfigError::check_probability("Elitism", self.elitism)?;
+        ConfigError::check_probability("MutationProb", self.mutation_prob)?;
+        ConfigError::check_probability("HeuristicSeed", self.heuristic_seed)?;
+        if let Some(final_approach) = &self.final_approach {
+            final_approach.validate()?;
+        }
+        if let Some(stagnation) = &self.stagnation {
+            stagnation.validate()?;
+        }
+        if let Some(adaptive_horizon) = &self.adaptive_horizon {
+            adaptive_horizon.validate()?;
+        }
+        if let Some(planner) = &self.planner {
+            planner.validate()?;
+        }
+        Ok(())
+    }
 }
 
 pub struct SolverSettings {
@@ -33,9 +118,13 @@ pub struct SolverSettings {
     pub initial_thrust: i32,
     pub elitism: f64,
     pub mutation_prob: f64,
+    pub unique_elites: bool,
+    pub encoding: EncodingKind,
+    /// Seed of the random generator, drawn from entropy if `None`
+    pub seed: Option<u64>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Chromosome {
     pub angles: AngleGenes,
     pub thrusts: ThrustGenes,
@@ -43,34 +132,41 @@ pub struct Chromosome {
 
 pub struct Solver {
     pub population: Vec<Chromosome>,
+    encoding: Box<dyn Encoding>,
     elitism: f64,
     mutation_prob: f64,
+    unique_elites: bool,
     initial_angle: Angle,
     initial_thrust: Thrust,
+    /// Drives the genetic operators, its state is saved in checkpoints
+    rng: ChaCha12Rng,
 }
 
-fn new_random_angle() -> Angle {
+pub(super) fn new_random_angle() -> Angle {
     rand::thread_rng().gen_range(ANGLE_STEP_RANGE)
 }
 
-fn new_random_thrust() -> Thrust {
+pub(super) fn new_random_thrust() -> Thrust {
     rand::thread_rng().gen_range(THRUST_STEP_RANGE)
 }
 
-fn clamp(v: i32, range: RangeInclusive<i32>) -> i32 {
+pub(super) fn clamp(v: i32, range: RangeInclusive<i32>) -> i32 {
     *range.start().max(range.end().min(&v))
 }
 
-fn crossed(
+pub(super) fn crossed(
     a: &[i32],
     b: &[i32],
     i: f64,
     clamp: impl Fn(i32) -> i32,
-) -> Result<(Vec<i32>, Vec<i32>), String> {
+) -> Result<(Vec<i32>, Vec<i32>), CrossoverError> {
     if a.len() != b.len() {
-        return Err(format!("a.len() != b.len() ({} != {})", a.len(), b.len()));
+        return Err(CrossoverError::SizeMismatch {
+            a: a.len(),
+            b: b.len(),
+        });
     } else if !(0f64..=1f64).contains(&i) {
-        return Err(format!("i out of range [0,1], i={i}"));
+        return Err(CrossoverError::CrossPointOutOfRange(i));
     }
 
     let (x, y) = a
@@ -121,21 +217,34 @@ impl Chromosome {
         ))
     }
 
-    pub fn crossover(&self, other: &Self, cross_point: f64) -> Result<(Self, Self), String> {
+    /// Commands of accumulated (absolute) genes, in order
+    pub fn commands(&self) -> Vec<super::Command> {
+        (0..self.angles.len())
+            .filter_map(|id| self.get_cmd(id))
+            .collect()
+    }
+
+    pub fn crossover(&self, other: &Self, cross_point: f64) -> Result<(Self, Self), SolverError> {
         let (angles_a, angles_b) = crossed(
             &self.angles,
             &other.angles,
             cross_point,
             clamp!(ANGLE_STEP_RANGE),
         )
-        .map_err(|e| format!("Failed to cross angles, cross point: {cross_point}\n{e}"))?;
+        .map_err(|source| SolverError::Crossover {
+            genes: "angles",
+            source,
+        })?;
         let (thrusts_a, thrusts_b) = crossed(
             &self.thrusts,
             &other.thrusts,
             cross_point,
             clamp!(THRUST_STEP_RANGE),
         )
-        .map_err(|e| format!("Failed to cross thrusts, cross point: {cross_point}\n{e}"))?;
+        .map_err(|source| SolverError::Crossover {
+            genes: "thrusts",
+            source,
+        })?;
         Ok((
             Self {
                 angles: angles_a,
@@ -148,100 +257,135 @@ impl Chromosome {
         ))
     }
 
+    /// Turns delta genes into absolute commands starting from the initial ones
+    pub fn accumulated(&self, initial_angle: i32, initial_thrust: i32) -> Self {
+        Self {
+            angles: accumulated(
+                initial_angle,
+                self.angles.iter().copied(),
+                clamp!(ANGLE_RANGE),
+            )
+            .collect(),
+            thrusts: accumulated(
+                initial_thrust,
+                self.thrusts.iter().copied(),
+                clamp!(THRUST_RANGE),
+            )
+            .collect(),
+        }
+    }
+
+    /// Inverse of [`Chromosome::accumulated`], deltas are clamped into step ranges and the result
+    /// is padded with zero deltas (repeating the last command) up to `size` genes
+    pub fn from_accumulated(
+        commands: &Self,
+        initial_angle: i32,
+        initial_thrust: i32,
+        size: usize,
+    ) -> Self {
+        let deltas = |initial: i32, values: &[i32], range: RangeInclusive<i32>| {
+            std::iter::once(initial)
+                .chain(values.iter().copied())
+                .zip(values)
+                .map(|(previous, value)| clamp(value - previous, range.clone()))
+                .chain(std::iter::repeat(0))
+                .take(size)
+                .collect()
+        };
+        Self {
+            angles: deltas(initial_angle, &commands.angles, ANGLE_STEP_RANGE),
+            thrusts: deltas(initial_thrust, &commands.thrusts, THRUST_STEP_RANGE),
+        }
+    }
+
     pub fn mutate(&mut self, mutation_prob: f64) -> Option<()> {
+        self.mutate_with(mutation_prob, &mut rand::thread_rng());
+        Some(())
+    }
+
+    /// [`Chromosome::mutate`] drawing from `rng`
+    pub fn mutate_with(&mut self, mutation_prob: f64, rng: &mut impl Rng) {
         self.angles
             .iter_mut()
             .zip(self.thrusts.iter_mut())
             .for_each(|(angle, thrust)| {
-                if rand::thread_rng().gen_range(0f64..1f64) < mutation_prob {
-                    *angle = clamp(new_random_angle(), ANGLE_STEP_RANGE);
-                    *thrust = clamp(new_random_thrust(), THRUST_STEP_RANGE);
+                if rng.gen_range(0f64..1f64) < mutation_prob {
+                    *angle = rng.gen_range(ANGLE_STEP_RANGE);
+                    *thrust = rng.gen_range(THRUST_STEP_RANGE);
                 }
             });
-        Some(())
     }
 }
 
 impl Solver {
-    pub fn try_new(settings: SolverSettings) -> Result<Self, String> {
-        if settings.elitism < 0f64 || settings.elitism > 1f64 {
-            return Err(format!("Elitism ({}) out of range [0,1]", settings.elitism));
-        }
-        if settings.mutation_prob < 0f64 || settings.mutation_prob > 1f64 {
-            return Err(format!(
-                "MutationProb ({}) out of range [0,1]",
-                settings.mutation_prob
-            ));
-        }
+    pub fn try_new(settings: SolverSettings) -> Result<Self, ConfigError> {
+        ConfigError::check_probability("Elitism", settings.elitism)?;
+        ConfigError::check_probability("MutationProb", settings.mutation_prob)?;
+        let encoding = settings.encoding.create_encoding();
+        let mut rng = settings
+            .seed
+            .map_or_else(ChaCha12Rng::from_entropy, ChaCha12Rng::seed_from_u64);
         let population: Vec<_> = (0..settings.population_size)
-            .map(|_| Chromosome::new_random(settings.chromosome_size))
+            .map(|_| encoding.new_random(settings.chromosome_size, &mut rng))
             .collect();
         Ok(Self {
             population,
+            encoding,
             elitism: settings.elitism,
             mutation_prob: settings.mutation_prob,
+            unique_elites: settings.unique_elites,
             initial_angle: settings.initial_angle,
             initial_thrust: settings.initial_thrust,
+            rng,
         })
     }
 
-    pub fn new_generation(&mut self, fitness: impl Iterator<Item = f64>) -> Result<(), String> {
-        let len_population_before = self.population.len();
-        let parents = self.choose_parents(fitness);
-        let n_children = len_population_before - parents.len();
-        let mut new_pop = self.mate(self.population.iter().collect(), n_children)?;
-        new_pop.extend(parents.iter().map(|c| (**c).clone()));
-        self.population = new_pop;
-        assert_eq!(len_population_before, self.population.len());
+    pub fn new_generation(
+        &mut self,
+        fitness: impl Iterator<Item = f64>,
+    ) -> Result<(), SolverError> {
+        let population: Vec<_> = self
+            .population
+            .iter()
+            .map(|genes| Encoded {
+                genes: genes.clone(),
+                encoding: self.encoding.as_ref(),
+            })
+            .collect();
+        self.population = evolve(
+            &population,
+            fitness,
+            self.elitism,
+            self.mutation_prob,
+            self.unique_elites,
+            &mut self.rng,
+        )?
+        .into_iter()
+        .map(|encoded| encoded.genes)
+        .collect();
         Ok(())
     }
 
-    fn choose_parents(&self, fitness: impl Iterator<Item = f64>) -> Vec<&Chromosome> {
-        let mut ranking = self.population.iter().zip(fitness).collect::<Vec<_>>();
-        ranking.sort_by(|(_, fitness1), (_, fitness2)| fitness1.total_cmp(fitness2).reverse());
+    pub fn encoding(&self) -> &dyn Encoding {
+        self.encoding.as_ref()
+    }
 
-        let n_best = (self.elitism * self.population.len() as f64) as usize;
-        ranking[..n_best].iter().map(|(c, _)| *c).collect()
+    pub fn rng_state(&self) -> RngState {
+        RngState::of(&self.rng)
     }
 
-    fn mate(
-        &self,
-        parents: Vec<&Chromosome>,
-        n_children: usize,
-    ) -> Result<Vec<Chromosome>, String> {
-        let new_population =
-            (0..n_children / 2).try_fold(Vec::new(), |mut new_population, _| {
-                let mut r = parents.choose_multiple(&mut rand::thread_rng(), 2);
-                let parent1 = r.next().ok_or("Can't get parent1")?;
-                let parent2 = r.next().ok_or("Can't get parent2")?;
-                let (mut c1, mut c2) =
-                    parent1.crossover(parent2, rand::thread_rng().gen_range(0f64..1f64))?;
-                c1.mutate(self.mutation_prob);
-                c2.mutate(self.mutation_prob);
-                new_population.push(c1);
-                new_population.push(c2);
-                Ok::<Vec<_>, String>(new_population)
-            })?;
-        Ok(new_population)
+    /// Continues the random sequence from `state`, e.g. saved in a checkpoint
+    pub fn restore_rng(&mut self, state: &RngState) {
+        self.rng = state.restore();
     }
 
     pub fn iter_accumulated_population(&self) -> impl Iterator<Item = Chromosome> + '_ {
-        self.population
-            .iter()
-            .map(|Chromosome { angles, thrusts }| Chromosome {
-                angles: accumulated(
-                    self.initial_angle,
-                    angles.iter().copied(),
-                    clamp!(ANGLE_RANGE),
-                )
-                .collect(),
-                thrusts: accumulated(
-                    self.initial_thrust,
-                    thrusts.iter().copied(),
-                    clamp!(THRUST_RANGE),
-                )
-                .collect(),
-            })
+        self.population.iter().map(|c| self.decode(c))
+    }
+
+    pub fn decode(&self, chromosome: &Chromosome) -> Chromosome {
+        self.encoding
+            .decode(chromosome, self.initial_angle, self.initial_thrust)
     }
 
     pub fn iter_population(&self) -> impl Iterator<Item = &Chromosome> {
@@ -249,87 +393,167 @@ impl Solver {
     }
 }
 
-fn landing_state_score(state: &crate::Landing) -> f64 {
-    use crate::Landing;
-    match state {
-        Landing::Correct => 1.,
-        Landing::WrongTerrain { .. } => 0.3,
-        Landing::NotVertical { .. } => 0.9,
-        Landing::TooFastVertical { .. } => 0.7,
-        Landing::TooFastHorizontal { .. } => 0.5,
+/// Genes evolved by [`evolve`]
+pub trait Genome: Clone {
+    /// Two children blending `self` and `other`, `cross_point` in range [0,1] is the blend ratio
+    fn crossover(&self, other: &Self, cross_point: f64) -> Result<(Self, Self), SolverError>;
+
+    fn mutate(&mut self, mutation_prob: f64, rng: &mut impl Rng);
+}
+
+impl Genome for Chromosome {
+    fn crossover(&self, other: &Self, cross_point: f64) -> Result<(Self, Self), SolverError> {
+        Chromosome::crossover(self, other, cross_point)
+    }
+
+    fn mutate(&mut self, mutation_prob: f64, rng: &mut impl Rng) {
+        self.mutate_with(mutation_prob, rng);
     }
 }
 
-#[derive(Default)]
-struct MaxErrors {
-    angle_error: Option<(f64, f64)>,
-    horizontal_speed_error: Option<(f64, f64)>,
-    vertical_speed_error: Option<(f64, f64)>,
-    terrain_dist_error: Option<(f64, f64)>,
+/// Genes evolved with the operators of their encoding
+#[derive(Clone)]
+struct Encoded<'a> {
+    genes: Chromosome,
+    encoding: &'a dyn Encoding,
 }
 
-fn update_min_max(a: &mut Option<(f64, f64)>, b: f64) {
-    *a = match a {
-        None => Some((b, b)),
-        Some((existing_min, existing_max)) => Some((existing_min.min(b), existing_max.max(b))),
-    };
+impl PartialEq for Encoded<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.genes == other.genes
+    }
 }
 
-fn get_min_max_errors<'a>(landing_results: impl Iterator<Item = &'a super::Landing>) -> MaxErrors {
-    landing_results.fold(MaxErrors::default(), |mut errors, landing_result| {
-        use super::Landing;
-        match landing_result {
-            Landing::NotVertical { error_abs, .. } => {
-                update_min_max(&mut errors.angle_error, *error_abs)
-            }
-            Landing::WrongTerrain { dist, .. } => {
-                update_min_max(&mut errors.terrain_dist_error, *dist)
-            }
-            Landing::TooFastHorizontal { error_abs, .. } => {
-                update_min_max(&mut errors.horizontal_speed_error, *error_abs)
-            }
-            Landing::TooFastVertical { error_abs, .. } => {
-                update_min_max(&mut errors.vertical_speed_error, *error_abs)
-            }
-            Landing::Correct => (),
-        };
-        errors
-    })
+impl Genome for Encoded<'_> {
+    fn crossover(&self, other: &Self, cross_point: f64) -> Result<(Self, Self), SolverError> {
+        let (a, b) = self
+            .encoding
+            .crossover(&self.genes, &other.genes, cross_point)?;
+        Ok((
+            Self {
+                genes: a,
+                encoding: self.encoding,
+            },
+            Self {
+                genes: b,
+                encoding: self.encoding,
+            },
+        ))
+    }
+
+    fn mutate(&mut self, mutation_prob: f64, rng: &mut impl Rng) {
+        self.encoding.mutate(&mut self.genes, mutation_prob, rng);
+    }
 }
 
-pub fn calculate_fitness(landing_results: &[super::Landing]) -> Option<Vec<f64>> {
-    use crate::Landing;
-    let max_errors = get_min_max_errors(landing_results.iter());
-    let normalized_score = |value, (min, max)| {
-        assert!(max >= min);
-        if max == min {
-            0.
-        } else {
-            (value - min) / (max - min)
+/// Next generation of a genetic algorithm: the `elitism` fraction of the best genomes survives,
+/// the rest is replaced by mutated children of random population members.
+///
+/// With `unique_elites` identical genomes survive only once, the next best distinct ones take their
+/// places.
+pub fn evolve<G: Genome + PartialEq>(
+    population: &[G],
+    fitness: impl Iterator<Item = f64>,
+    elitism: f64,
+    mutation_prob: f64,
+    unique_elites: bool,
+    rng: &mut impl Rng,
+) -> Result<Vec<G>, SolverError> {
+    let parents = choose_parents(population, fitness, elitism, unique_elites);
+    let n_children = population.len() - parents.len();
+    let mut new_pop = mate(population, n_children, mutation_prob, rng)?;
+    new_pop.extend(parents.into_iter().cloned());
+    assert_eq!(population.len(), new_pop.len());
+    Ok(new_pop)
+}
+
+fn choose_parents<G: PartialEq>(
+    population: &[G],
+    fitness: impl Iterator<Item = f64>,
+    elitism: f64,
+    unique: bool,
+) -> Vec<&G> {
+    let mut ranking = population.iter().zip(fitness).collect::<Vec<_>>();
+    ranking.sort_by(|(_, fitness1), (_, fitness2)| fitness1.total_cmp(fitness2).reverse());
+
+    let n_best = (elitism * population.len() as f64) as usize;
+    if !unique {
+        return ranking[..n_best].iter().map(|(c, _)| *c).collect();
+    }
+    let mut parents: Vec<&G> = Vec::with_capacity(n_best);
+    for (c, _) in ranking {
+        if parents.len() == n_best {
+            break;
         }
-    };
-    let base_score = |result: &Landing| {
-        Some(match result {
-            Landing::Correct => 0.,
-            Landing::NotVertical { error_abs, .. } => {
-                normalized_score(*error_abs, max_errors.angle_error?)
-            }
-            Landing::TooFastHorizontal { error_abs, .. } => {
-                normalized_score(*error_abs, max_errors.horizontal_speed_error?)
-            }
-            Landing::TooFastVertical { error_abs, .. } => {
-                normalized_score(*error_abs, max_errors.vertical_speed_error?)
-            }
-            Landing::WrongTerrain { dist } => {
-                normalized_score(*dist, max_errors.terrain_dist_error?)
-            }
-        })
-    };
+        if !parents.contains(&c) {
+            parents.push(c);
+        }
+    }
+    parents
+}
 
-    landing_results
-        .iter()
-        .map(|result| Some((1. - base_score(result)?) * landing_state_score(result)))
-        .collect()
+/// `n_children` mutated children of random pairs of `parents`, each pair has two children so the
+/// last one is dropped when `n_children` is odd
+fn mate<G: Genome>(
+    parents: &[G],
+    n_children: usize,
+    mutation_prob: f64,
+    rng: &mut impl Rng,
+) -> Result<Vec<G>, SolverError> {
+    let mut new_population =
+        (0..n_children.div_ceil(2)).try_fold(Vec::new(), |mut new_population, _| {
+            let mut r = parents.choose_multiple(rng, 2);
+            let parent1 = r.next().ok_or(SolverError::EmptyPopulation)?;
+            let parent2 = r.next().ok_or(SolverError::EmptyPopulation)?;
+            let (mut c1, mut c2) = parent1.crossover(parent2, rng.gen_range(0f64..1f64))?;
+            c1.mutate(mutation_prob, rng);
+            c2.mutate(mutation_prob, rng);
+            new_population.push(c1);
+            new_population.push(c2);
+            Ok::<Vec<_>, SolverError>(new_population)
+        })?;
+    new_population.truncate(n_children);
+    Ok(new_population)
+}
+
+#[cfg(test)]
+mod parents_test {
+    use super::choose_parents;
+
+    #[test]
+    fn unique_elites() {
+        let population = [1, 1, 1, 2, 3];
+        let fitness = [0.9, 0.9, 0.9, 0.5, 0.1];
+        let parents = choose_parents(&population, fitness.into_iter(), 0.6, false);
+        assert_eq!(parents, vec![&1, &1, &1]);
+        let parents = choose_parents(&population, fitness.into_iter(), 0.6, true);
+        assert_eq!(parents, vec![&1, &2, &3]);
+    }
+}
+
+#[cfg(test)]
+mod mating_test {
+    use super::{evolve, mate, Chromosome};
+
+    #[test]
+    fn odd_number_of_children() {
+        let population: Vec<_> = (0..5).map(|_| Chromosome::new_random(4)).collect();
+        let mut rng = rand::thread_rng();
+        for n_children in [0, 1, 3, 4] {
+            let children = mate(&population, n_children, 0.1, &mut rng).unwrap();
+            assert_eq!(children.len(), n_children);
+        }
+        // 2 elites out of 5 leave 3 children to mate
+        let next = evolve(
+            &population,
+            [1., 2., 3., 4., 5.].into_iter(),
+            0.4,
+            0.1,
+            false,
+            &mut rng,
+        );
+        assert_eq!(next.unwrap().len(), 5);
+    }
 }
 
 #[cfg(test)]
@@ -391,7 +615,7 @@ mod crossing_test {
 
 #[cfg(test)]
 mod accumulation_test {
-    use super::accumulated;
+    use super::{accumulated, Chromosome};
 
     fn pass(x: i32) -> i32 {
         x
@@ -417,4 +641,17 @@ mod accumulation_test {
         let a: Vec<_> = accumulated(3, a.iter().copied(), |x| x.min(6)).collect();
         assert_eq!(a, vec![4, 5, 6, 6]);
     }
+
+    #[test]
+    fn from_accumulated_round_trip() {
+        let genes = Chromosome {
+            angles: vec![15, -3, 0, -15],
+            thrusts: vec![1, 1, 0, -1],
+        };
+        let commands = genes.accumulated(-45, 2);
+        assert_eq!(Chromosome::from_accumulated(&commands, -45, 2, 4), genes);
+        let padded = Chromosome::from_accumulated(&commands, -45, 2, 6);
+        assert_eq!(padded.angles, vec![15, -3, 0, -15, 0, 0]);
+        assert_eq!(padded.accumulated(-45, 2).angles[5], -48);
+    }
 }
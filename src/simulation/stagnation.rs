@@ -0,0 +1,168 @@
+use std::str::FromStr;
+
+use crate::ConfigError;
+
+/// Best fitness has to grow by more than that to count as an improvement
+const MIN_IMPROVEMENT: f64 = 1e-9;
+
+/// How the population is restarted once the search stagnates, the elites are always kept intact
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StagnationResponse {
+    /// Fills the population with copies of the elites mutated with a high probability
+    #[default]
+    CataclysmicMutation,
+    /// Replaces the population with random chromosomes
+    Rerandomize,
+    /// Replaces the population with perturbed copies of the autopilot's flight
+    Reseed,
+}
+
+impl FromStr for StagnationResponse {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "CataclysmicMutation" => Ok(Self::CataclysmicMutation),
+            "Rerandomize" => Ok(Self::Rerandomize),
+            "Reseed" => Ok(Self::Reseed),
+            _ => Err(ConfigError::UnknownVariant {
+                key: "Response",
+                value: s.to_string(),
+                expected: &["CataclysmicMutation", "Rerandomize", "Reseed"],
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StagnationSettings {
+    /// Number of generations without improvement of the best fitness triggering a restart
+    pub generations: usize,
+    /// Mean pairwise gene distance (see [`super::mean_gene_distance`]) below which the
+    /// population is restarted
+    pub min_diversity: f64,
+    pub response: StagnationResponse,
+    /// Fraction of the best chromosomes surviving a restart
+    pub elitism: f64,
+    /// Gene mutation probability of the cataclysmic mutation
+    pub mutation_prob: f64,
+}
+
+impl Default for StagnationSettings {
+    fn default() -> Self {
+        Self {
+            generations: 50,
+            min_diversity: 0.,
+            response: StagnationResponse::default(),
+            elitism: 0.05,
+            mutation_prob: 0.3,
+        }
+    }
+}
+
+impl StagnationSettings {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        ConfigError::check_range("MinDiversity", self.min_diversity, 0., f64::INFINITY)?;
+        ConfigError::check_probability("Elitism", self.elitism)?;
+        ConfigError::check_probability("MutationProb", self.mutation_prob)
+    }
+}
+
+/// Memory of past generations: best and mean fitness of each of them and the generations since
+/// the best absolute fitness last improved
+#[derive(Clone, Debug, Default)]
+pub struct FitnessHistory {
+    best: Vec<f64>,
+    mean: Vec<f64>,
+    /// Best [`super::FitnessFunction::calculate_comparable`] recorded, fitness is normalized within each generation so
+    /// it can't tell improvements
+    best_so_far: Option<f64>,
+    stalled: usize,
+    restarts: usize,
+}
+
+impl FitnessHistory {
+    /// Records `fitness` of a generation whose best lander scored `best_absolute`, see
+    /// [`super::FitnessFunction::calculate_comparable`]
+    pub fn record(&mut self, fitness: &[f64], best_absolute: f64) {
+        let Some(best) = fitness.iter().copied().max_by(f64::total_cmp) else {
+            return;
+        };
+        self.best.push(best);
+        self.mean
+            .push(fitness.iter().sum::<f64>() / fitness.len() as f64);
+        if self
+            .best_so_far
+            .is_none_or(|best_so_far| best_absolute > best_so_far + MIN_IMPROVEMENT)
+        {
+            self.best_so_far = Some(best_absolute);
+            self.stalled = 0;
+        } else {
+            self.stalled += 1;
+        }
+    }
+
+    /// Whether the last recorded generation calls for a restart, `diversity` is the mean pairwise
+    /// gene distance of its population
+    pub fn is_stagnating(&self, settings: &StagnationSettings, diversity: f64) -> bool {
+        self.stalled >= settings.generations || diversity < settings.min_diversity
+    }
+
+    /// Marks a restart, the stagnation count starts over
+    pub fn restarted(&mut self) {
+        self.stalled = 0;
+        self.restarts += 1;
+    }
+
+    /// Best fitness of each generation
+    pub fn best(&self) -> &[f64] {
+        &self.best
+    }
+
+    /// Mean fitness of each generation
+    pub fn mean(&self) -> &[f64] {
+        &self.mean
+    }
+
+    /// Generations recorded since the best absolute fitness last improved or the last restart
+    pub fn stalled(&self) -> usize {
+        self.stalled
+    }
+
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+}
+
+#[cfg(test)]
+mod stagnation_test {
+    use super::*;
+
+    #[test]
+    fn detects_stalled_best_fitness() {
+        let settings = StagnationSettings {
+            generations: 2,
+            ..Default::default()
+        };
+        let mut history = FitnessHistory::default();
+        history.record(&[0.5, 0.1], 0.2);
+        // normalized fitness doesn't count, the absolute one does
+        history.record(&[0.5, 0.3], 0.3);
+        assert!(!history.is_stagnating(&settings, 1.));
+        history.record(&[0.7, 0.5], 0.3);
+        history.record(&[0.6, 0.6], 0.25);
+        assert!(history.is_stagnating(&settings, 1.));
+        assert_eq!(history.best(), &[0.5, 0.5, 0.7, 0.6]);
+        assert_eq!(history.mean(), &[0.3, 0.4, 0.6, 0.6]);
+
+        history.restarted();
+        assert!(!history.is_stagnating(&settings, 1.));
+        assert!(history.is_stagnating(
+            &StagnationSettings {
+                min_diversity: 0.1,
+                ..settings
+            },
+            0.05
+        ));
+    }
+}
@@ -1,29 +1,845 @@
-use simulation::{App, LanderState, Settings, Terrain};
+use simulation::{
+    AdaptiveHorizonSettings, Algorithm, App, AppBuilder, Command, ConfigError, DefaultFitness,
+    EncodingKind, Error, FitnessFunction, FlightState, HistoryRecording, LanderHistory,
+    LanderState, Landing, LandingCriteria, LocalSearchSettings, NeuralNetwork, ParseError,
+    ParseErrorKind, PhysicsSettings, PlannerSettings, Settings, SimulationError,
+    StagnationResponse, StagnationSettings, Terrain,
+};
 
 fn simple_app() -> App {
-    App::try_new(
+    simple_app_with(test_settings())
+}
+
+/// Settings the tests start from, small enough to evolve quickly
+fn test_settings() -> Settings {
+    Settings {
+        population_size: 300,
+        chromosome_size: 50,
+        elitism: 0.2,
+        mutation_prob: 0.01,
+        ..Default::default()
+    }
+}
+
+fn simple_app_with(settings: Settings) -> App {
+    simple_builder(settings).build().unwrap()
+}
+
+fn simple_builder(settings: Settings) -> AppBuilder {
+    AppBuilder::new(
         LanderState::default()
             .with_y(1000.)
             .with_x(500.)
             .with_fuel(1000),
         Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]),
+    )
+    .with_settings(settings)
+}
+
+/// Lander without fuel falling from high above, it can't land correctly
+fn unsolvable_builder(settings: Settings) -> AppBuilder {
+    AppBuilder::new(
+        LanderState::default()
+            .with_y(2500.)
+            .with_x(500.)
+            .with_fuel(0),
+        Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]),
+    )
+    .with_settings(settings)
+}
+
+fn evolve(app: &mut App, generations: usize) {
+    app.generations()
+        .take(generations)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+}
+
+#[test]
+fn simple_run() {
+    let mut app = simple_app();
+
+    for (i, generation) in app.generations().take(10).enumerate() {
+        generation.unwrap_or_else(|e| panic!("Failed on {i} iteration: {e}"));
+    }
+}
+
+#[test]
+fn solve_for_respects_budget() {
+    let mut app = unsolvable_builder(Settings {
+        population_size: 100,
+        chromosome_size: 30,
+        ..Default::default()
+    })
+    .build()
+    .unwrap();
+    let slowest_generation = std::sync::Arc::new(std::sync::Mutex::new(std::time::Duration::ZERO));
+    let mut generation_start = std::time::Instant::now();
+    app.add_observer({
+        let slowest_generation = slowest_generation.clone();
+        move |_| {
+            let mut slowest = slowest_generation.lock().unwrap();
+            *slowest = (*slowest).max(generation_start.elapsed());
+            generation_start = std::time::Instant::now();
+        }
+    });
+    let budget = std::time::Duration::from_millis(300);
+
+    let solution = app.solve_for(budget).unwrap();
+
+    assert!(!solution.landed);
+    assert!(solution.stats.generations > 1);
+    // elites fly the same commands again, their flights are reused rather than simulated
+    assert_eq!(
+        solution.stats.simulations,
+        solution.stats.generations * 100 - app.get_cache_stats().full_hits
+    );
+    // the last generation is started as long as the slowest one so far fits in the budget
+    assert!(solution.stats.elapsed <= budget + *slowest_generation.lock().unwrap());
+}
+
+#[test]
+fn optimize_fuel_keeps_landing() {
+    let mut app = simple_app();
+
+    let optimization = app
+        .optimize_fuel_for(std::time::Duration::from_millis(500))
+        .unwrap()
+        .expect("the flat map is solved");
+
+    let first = optimization.improvements.first().unwrap();
+    assert!(optimization.fuel >= first.fuel);
+    assert_eq!(
+        optimization.improvements.last().unwrap().fuel,
+        optimization.fuel
+    );
+    let replay = app
+        .replay(&optimization.chromosome.commands(), 1000)
+        .unwrap();
+    assert!(matches!(replay.landing, Some(Landing::Correct)));
+    assert_eq!(replay.history.last_state().unwrap().fuel, optimization.fuel);
+}
+
+#[test]
+fn multi_objective_run() {
+    let mut app = simple_app_with(Settings {
+        multi_objective: true,
+        ..test_settings()
+    });
+
+    evolve(&mut app, 3);
+    app.run().unwrap();
+    assert!(!app.get_pareto_front().is_empty());
+}
+
+#[test]
+fn alternative_algorithms_run() {
+    for algorithm in [
+        Algorithm::CmaEs,
+        Algorithm::ParticleSwarm,
+        Algorithm::SimulatedAnnealing,
+    ] {
+        let mut app = simple_app_with(Settings {
+            algorithm,
+            ..test_settings()
+        });
+
+        evolve(&mut app, 5);
+    }
+}
+
+#[test]
+fn local_search_run() {
+    let mut app = simple_app_with(Settings {
+        local_search: Some(LocalSearchSettings {
+            top_k: 3,
+            max_steps: 10,
+        }),
+        ..test_settings()
+    });
+
+    let solution = app
+        .solve_for(std::time::Duration::from_millis(100))
+        .unwrap();
+    assert!(
+        solution.stats.simulations
+            > solution.stats.generations * 300 - app.get_cache_stats().full_hits
+    );
+}
+
+#[test]
+fn heuristic_seed_run() {
+    let mut app = simple_app_with(Settings {
+        heuristic_seed: 0.1,
+        ..test_settings()
+    });
+
+    assert!(app.run().unwrap().is_some());
+}
+
+#[test]
+fn network_weights_round_trip() {
+    let network = NeuralNetwork::new_random(&[4]);
+    let path = std::env::temp_dir().join(format!("network_{}.json", std::process::id()));
+    simulation::init::json::save_network(&network, &path).unwrap();
+    let loaded = simulation::init::json::parse_network(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.layers(), network.layers());
+    assert!(loaded
+        .weights()
+        .iter()
+        .zip(network.weights())
+        .all(|(a, b)| (a - b).abs() < 1e-12));
+}
+
+#[test]
+fn final_approach_reported() {
+    let mut app = simple_app();
+    app.run().unwrap();
+    // off by default
+    assert!(app.get_final_approach_steps().iter().all(Option::is_none));
+
+    let mut app = simple_app_with(Settings {
+        final_approach: Some(Default::default()),
+        ..test_settings()
+    });
+
+    app.run().unwrap();
+    // the whole terrain is a landing zone, so landers touching it end with final approach
+    let steps = app.get_final_approach_steps();
+    let landed_on_terrain = app
+        .get_current_states()
+        .map(|state| !matches!(state, FlightState::Landed(Landing::WrongTerrain { .. })));
+    assert!(steps
+        .iter()
+        .zip(landed_on_terrain)
+        .all(|(step, landed)| !landed || step.is_some()));
+}
+
+#[test]
+fn checkpoint_resumes_exactly() {
+    let mut app = simple_app();
+    evolve(&mut app, 3);
+    let path = std::env::temp_dir().join(format!("checkpoint_{}.json", std::process::id()));
+    app.save_checkpoint(&path).unwrap();
+    let mut resumed = App::try_from_checkpoint_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(resumed.get_population_id(), 3);
+
+    for app in [&mut app, &mut resumed] {
+        evolve(app, 3);
+    }
+    assert!(app.get_population().eq(resumed.get_population()));
+}
+
+#[test]
+fn parse_errors_locate_the_setting() {
+    let path = std::env::temp_dir().join(format!("settings_{}.json", std::process::id()));
+    let parse = |settings: &str| {
+        std::fs::write(&path, settings).unwrap();
+        simulation::init::json::parse_settings(&path).unwrap_err()
+    };
+    let missing = parse(r#"{"PopulationSize": 10, "ChromosomeSize": 10, "Elitism": 0.2}"#);
+    let unknown = parse(
+        r#"{"PopulationSize": 10, "ChromosomeSize": 10, "Elitism": 0.2, "MutationProb": 0.01,
+            "Stagnation": {"Response": "Restart"}}"#,
+    );
+    let out_of_range = parse(
+        r#"{"PopulationSize": 10, "ChromosomeSize": 10, "Elitism": 0.2, "MutationProb": 0.01,
+            "Stagnation": {"Elitism": 2}}"#,
+    );
+    let zero_cell = parse(
+        r#"{"PopulationSize": 10, "ChromosomeSize": 10, "Elitism": 0.2, "MutationProb": 0.01,
+            "Planner": {"CellSize": 0}}"#,
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(missing.file.as_deref(), Some(path.as_path()));
+    assert!(matches!(&missing.kind, ParseErrorKind::MissingKey(key) if key == "/MutationProb"));
+    let ParseError {
+        kind: ParseErrorKind::InvalidSetting { key, source },
+        ..
+    } = unknown
+    else {
+        panic!("expected an invalid setting, got {unknown:?}");
+    };
+    assert_eq!(key, "/Stagnation/Response");
+    assert!(matches!(source, ConfigError::UnknownVariant { value, .. } if value == "Restart"));
+    assert!(matches!(
+        &out_of_range.kind,
+        ParseErrorKind::InvalidSetting {
+            key,
+            source: ConfigError::OutOfRange { value, .. },
+        } if key == "/Stagnation/Elitism" && *value == 2.
+    ));
+    assert!(matches!(
+        &zero_cell.kind,
+        ParseErrorKind::InvalidSetting { key, .. } if key == "/Planner/CellSize"
+    ));
+}
+
+#[test]
+fn checkpoint_requires_genetic_algorithm() {
+    let app = simple_app_with(Settings {
+        algorithm: Algorithm::CmaEs,
+        ..test_settings()
+    });
+    assert!(matches!(
+        app.checkpoint(),
+        Err(ConfigError::Unsupported {
+            algorithm: Algorithm::CmaEs,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn stagnation_restarts_population() {
+    for response in [
+        StagnationResponse::CataclysmicMutation,
+        StagnationResponse::Rerandomize,
+        StagnationResponse::Reseed,
+    ] {
+        let mut app = simple_app_with(Settings {
+            stagnation: Some(StagnationSettings {
+                // never diverse enough, so every generation restarts
+                min_diversity: 2.,
+                response,
+                ..Default::default()
+            }),
+            ..test_settings()
+        });
+        evolve(&mut app, 3);
+        let history = app.get_fitness_history();
+        assert_eq!(history.best().len(), 3);
+        assert_eq!(history.restarts(), 3, "{response:?}");
+    }
+}
+
+#[test]
+fn stagnation_restarts_without_improvement() {
+    // without fuel every lander falls the same way, so the best landing never improves
+    let mut app = AppBuilder::new(
+        LanderState::default().with_y(1000.).with_x(500.),
+        Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]),
+    )
+    .with_settings(Settings {
+        population_size: 50,
+        chromosome_size: 20,
+        stagnation: Some(StagnationSettings {
+            generations: 2,
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+    .build()
+    .unwrap();
+    evolve(&mut app, 6);
+    assert_eq!(app.get_fitness_history().restarts(), 2);
+}
+
+#[test]
+fn diversity_measured_per_generation() {
+    let mut app = simple_app_with(Settings {
+        unique_elites: true,
+        ..test_settings()
+    });
+    evolve(&mut app, 3);
+    let history = app.get_diversity_history();
+    assert_eq!(history.len(), 3);
+    assert!(history
+        .iter()
+        .all(|d| (0f64..=1.).contains(&d.gene_distance) && d.angle_entropy.len() == 50));
+    // the initial population is random
+    assert!(history[0].mean_entropy() > 0.5);
+}
+
+#[test]
+fn alternative_encodings_run() {
+    for encoding in [
+        EncodingKind::Absolute,
+        EncodingKind::Hold,
+        EncodingKind::Spline,
+    ] {
+        let mut app = simple_app_with(Settings {
+            encoding,
+            heuristic_seed: 0.1,
+            ..test_settings()
+        });
+        evolve(&mut app, 3);
+    }
+    assert!(matches!(
+        App::try_new(
+            LanderState::default(),
+            Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]),
+            Settings {
+                algorithm: Algorithm::CmaEs,
+                encoding: EncodingKind::Hold,
+                ..Default::default()
+            },
+        ),
+        Err(Error::Config(ConfigError::Unsupported { .. }))
+    ));
+}
+
+#[test]
+fn adaptive_horizon_grows_short_chromosomes() {
+    let mut app = App::try_new(
+        LanderState::default().with_y(1000.).with_x(500.),
+        Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]),
         Settings {
-            population_size: 300,
-            chromosome_size: 50,
-            elitism: 0.2,
-            mutation_prob: 0.01,
+            population_size: 50,
+            // far shorter than a fall from 1000m
+            chromosome_size: 10,
+            adaptive_horizon: Some(AdaptiveHorizonSettings {
+                min_size: 5,
+                ..Default::default()
+            }),
+            ..Default::default()
         },
     )
-    .unwrap()
+    .unwrap();
+    evolve(&mut app, 2);
+    assert_eq!(app.get_horizon(), 17);
+    assert!(app
+        .get_population()
+        .all(|c| c.angles.len() == 17 && c.thrusts.len() == 17));
+    evolve(&mut app, 1);
 }
 
 #[test]
-fn simple_run() {
+fn planned_corridor_seeds_cave_landing() {
+    let (lander, terrain) = simulation::init::json::parse_sim("examples/sim4.json").unwrap();
+    let mut app = App::try_new(
+        lander,
+        terrain,
+        Settings {
+            chromosome_size: 300,
+            heuristic_seed: 0.1,
+            planner: Some(PlannerSettings::default()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(app.get_corridor().is_some_and(|c| c.len() > 1));
+    // the intact copy of the flight through the corridor lands
+    assert!(app.run().unwrap().is_some());
+}
+
+#[test]
+fn cached_flights_match_simulation() {
+    let mut app = simple_app();
+    evolve(&mut app, 5);
+    app.run().unwrap();
+    let stats = app.get_cache_stats();
+    assert!(stats.full_hits > 0 && stats.reused_steps > 0);
+
+    let routes: Vec<Vec<_>> = app.get_routes().map(|r| r.collect()).collect();
+    let states: Vec<_> = app.get_current_states().cloned().collect();
+    for ((mut commands, route), state) in app.get_population_accumulated().zip(routes).zip(states) {
+        let (_, history, landing) = app.fly(&mut commands, 1000).unwrap();
+        let simulated: Vec<_> = history.iter_history().collect();
+        assert_eq!(format!("{simulated:?}"), format!("{route:?}"));
+        assert_eq!(
+            format!("{:?}", FlightState::Landed(landing.unwrap())),
+            format!("{state:?}")
+        );
+    }
+}
+
+#[test]
+fn portfolio_returns_best_member() {
+    let settings = Settings {
+        population_size: 50,
+        chromosome_size: 20,
+        ..Default::default()
+    };
+    let budget = std::time::Duration::from_millis(300);
+    let portfolio = simulation::solve_portfolio(
+        vec![
+            unsolvable_builder(settings.clone()),
+            unsolvable_builder(settings),
+        ],
+        budget,
+    )
+    .unwrap();
+
+    assert!(!portfolio.solution.landed);
+    assert!(portfolio.solution.fitness.is_finite());
+    assert_eq!(portfolio.member_stats.len(), 2);
+    assert!(portfolio
+        .member_stats
+        .iter()
+        .all(|stats| stats.generations >= 1));
+}
+
+#[test]
+fn portfolio_stops_members_after_landing() {
+    let settings = Settings {
+        population_size: 50,
+        chromosome_size: 20,
+        ..Default::default()
+    };
+    let budget = std::time::Duration::from_secs(60);
+    let portfolio = simulation::solve_portfolio(
+        vec![
+            unsolvable_builder(settings.clone()),
+            simple_builder(settings),
+        ],
+        budget,
+    )
+    .unwrap();
+
+    assert!(portfolio.solution.landed);
+    assert_eq!(portfolio.member, 1);
+    assert!(portfolio.member_stats[0].elapsed < budget / 2);
+}
+
+#[test]
+fn portfolio_stops_members_after_error() {
+    let settings = Settings {
+        population_size: 50,
+        chromosome_size: 20,
+        ..Default::default()
+    };
+    let budget = std::time::Duration::from_secs(60);
+    let started = std::time::Instant::now();
+    let result = simulation::solve_portfolio(
+        vec![
+            unsolvable_builder(settings.clone()),
+            unsolvable_builder(settings).with_gravity(-1.),
+        ],
+        budget,
+    );
+
+    assert!(matches!(
+        result,
+        Err(Error::Config(ConfigError::OutOfRange {
+            key: "Gravity",
+            ..
+        }))
+    ));
+    assert!(started.elapsed() < budget / 2);
+}
+
+#[test]
+fn builder_seed_repeats_run() {
+    let build = || {
+        simple_builder(test_settings())
+            .with_gravity(1.62)
+            .with_power_max(3)
+            .with_angle_limit(45.)
+            .with_landing(LandingCriteria {
+                max_vertical_speed: 20.,
+                max_horizontal_speed: 10.,
+            })
+            .with_seed(7)
+            .build()
+            .unwrap()
+    };
+    let (mut app, mut repeated) = (build(), build());
+    for app in [&mut app, &mut repeated] {
+        evolve(app, 3);
+    }
+    assert!(app.get_population().eq(repeated.get_population()));
+    assert!(app
+        .get_routes()
+        .flatten()
+        .all(|state| state.power <= 3 && state.angle.abs() <= 45.));
+    assert!(matches!(
+        app.checkpoint(),
+        Err(ConfigError::Requires {
+            setting: "Checkpoint",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn builder_seed_repeats_swarm_and_annealing() {
+    for algorithm in [Algorithm::ParticleSwarm, Algorithm::SimulatedAnnealing] {
+        let build = |seed| {
+            simple_builder(test_settings())
+                .with_algorithm(algorithm)
+                .with_seed(seed)
+                .build()
+                .unwrap()
+        };
+        let (mut app, mut repeated, mut other) = (build(7), build(7), build(8));
+        for app in [&mut app, &mut repeated, &mut other] {
+            evolve(app, 3);
+        }
+        assert!(app.get_population().eq(repeated.get_population()));
+        assert!(!app.get_population().eq(other.get_population()));
+    }
+}
+
+/// Ranks landers the other way round between generations
+struct InvertedComparable(DefaultFitness);
+
+impl FitnessFunction for InvertedComparable {
+    fn calculate(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        self.0.calculate(results)
+    }
+
+    fn calculate_comparable(&self, results: &[(&LanderHistory, &Landing)]) -> Option<Vec<f64>> {
+        let fitness = self.0.calculate_comparable(results)?;
+        Some(fitness.into_iter().map(|f| -f).collect())
+    }
+}
+
+#[test]
+fn custom_fitness_ranks_swarm_and_annealing() {
+    for algorithm in [Algorithm::ParticleSwarm, Algorithm::SimulatedAnnealing] {
+        let build = || {
+            simple_builder(test_settings())
+                .with_algorithm(algorithm)
+                .with_seed(7)
+                .build()
+                .unwrap()
+        };
+        let mut app = build();
+        let mut inverted = build().with_fitness_function(InvertedComparable(Default::default()));
+        for app in [&mut app, &mut inverted] {
+            evolve(app, 3);
+        }
+        assert!(!app.get_population().eq(inverted.get_population()));
+    }
+}
+
+#[test]
+fn builder_validates_options() {
+    let error = |builder: AppBuilder| match builder.build() {
+        Err(Error::Config(e)) => e,
+        Err(e) => panic!("unexpected error {e}"),
+        Ok(_) => panic!("invalid options accepted"),
+    };
+    assert!(matches!(
+        error(simple_builder(test_settings()).with_power_max(5)),
+        ConfigError::OutOfRange {
+            key: "PowerMax",
+            ..
+        }
+    ));
+    // without gravity a hovering lander would never land
+    assert!(matches!(
+        error(simple_builder(test_settings()).with_gravity(0.)),
+        ConfigError::OutOfRange { key: "Gravity", .. }
+    ));
+    assert!(matches!(
+        error(
+            simple_builder(test_settings())
+                .with_algorithm(Algorithm::CmaEs)
+                .with_seed(1)
+        ),
+        ConfigError::Unsupported { .. }
+    ));
+    assert!(matches!(
+        error(
+            simple_builder(Settings {
+                stagnation: Some(StagnationSettings::default()),
+                ..test_settings()
+            })
+            .with_history(HistoryRecording {
+                fitness: true,
+                diversity: false,
+            })
+        ),
+        ConfigError::Requires {
+            setting: "Stagnation",
+            ..
+        }
+    ));
+    assert!(matches!(
+        error(simple_builder(Settings {
+            stagnation: Some(StagnationSettings {
+                elitism: 2.,
+                ..Default::default()
+            }),
+            ..test_settings()
+        })),
+        ConfigError::OutOfRange { key: "Elitism", .. }
+    ));
+    assert!(matches!(
+        error(simple_builder(Settings {
+            planner: Some(PlannerSettings {
+                cell_size: 0.,
+                ..Default::default()
+            }),
+            ..test_settings()
+        })),
+        ConfigError::OutOfRange {
+            key: "CellSize",
+            ..
+        }
+    ));
+    assert!(matches!(
+        error(simple_builder(Settings {
+            adaptive_horizon: Some(AdaptiveHorizonSettings {
+                min_size: 100,
+                max_size: 50,
+                ..Default::default()
+            }),
+            ..test_settings()
+        })),
+        ConfigError::OutOfRange { key: "MinSize", .. }
+    ));
+
+    let mut app = simple_builder(test_settings())
+        .with_history(HistoryRecording {
+            fitness: false,
+            diversity: false,
+        })
+        .build()
+        .unwrap();
+    evolve(&mut app, 1);
+    assert!(app.get_diversity_history().is_empty());
+}
+
+#[test]
+fn generation_reported_to_observers() {
+    let mut app = simple_app();
+    let observed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let reports = observed.clone();
+    app.add_observer(move |report| reports.lock().unwrap().push(report.clone()));
+    let returned = app
+        .generations()
+        .take(3)
+        .map(|generation| generation.map(|generation| generation.report))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(*observed.lock().unwrap(), returned);
+    let last = returned.last().unwrap();
+    assert_eq!(last.generation, 2);
+    assert_eq!(last.landings.total(), 300);
+    assert!(last.best_fitness >= last.median_fitness);
+    assert!(last.best_touchdown.is_some());
+}
+
+#[test]
+fn generations_cancelled() {
     let mut app = simple_app();
+    let cancel = std::sync::atomic::AtomicBool::new(false);
+    let mut generations = app
+        .generations()
+        .with_cancel(&cancel)
+        .with_best_chromosome();
+    let generation = generations.next().unwrap().unwrap();
+    assert_eq!(generation.report.generation, 0);
+    assert!(generation.best_chromosome.is_some());
+    cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+    assert!(generations.next().is_none());
+    assert_eq!(app.get_population_id(), 1);
+}
+
+#[test]
+fn found_landing_replayed() {
+    let mut app = simple_app_with(Settings {
+        heuristic_seed: 0.1,
+        ..test_settings()
+    });
+    let landed = app.run().unwrap().unwrap();
+    let replay = app.replay(&landed.commands(), 1000).unwrap();
+    assert!(matches!(replay.landing, Some(Landing::Correct)));
+    let touchdown = replay.touchdown.unwrap();
+    assert!(touchdown.vertical_speed >= 0. && touchdown.horizontal_speed >= 0.);
+    assert_eq!(touchdown.tilt, 0.);
+}
+
+#[test]
+fn free_fall_replayed() {
+    let replay = |commands: &[Command], landing: &LandingCriteria| {
+        simulation::replay_flight(
+            LanderState::default().with_y(1000.).with_x(500.),
+            Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]),
+            &PhysicsSettings::default(),
+            landing,
+            commands,
+            1000,
+        )
+    };
+    let free_fall = replay(&[Command::new(0., 0)], &LandingCriteria::default()).unwrap();
+    assert!(matches!(
+        free_fall.landing,
+        Some(Landing::TooFastVertical { .. })
+    ));
+    assert!(free_fall.touchdown.unwrap().vertical_speed < 0.);
+    assert!(free_fall.history.last_state().unwrap().y.abs() < 1e-9);
+
+    assert!(matches!(
+        replay(&[], &LandingCriteria::default()),
+        Err(Error::Simulation(SimulationError::MissingCommand { .. }))
+    ));
+    assert!(matches!(
+        replay(
+            &[Command::new(0., 0)],
+            &LandingCriteria {
+                max_vertical_speed: -1.,
+                ..Default::default()
+            }
+        ),
+        Err(Error::Config(ConfigError::OutOfRange { .. }))
+    ));
+}
+
+#[test]
+fn invalid_command_replay_fails() {
+    let replay = |commands: &[Command]| {
+        simulation::replay_flight(
+            LanderState::default().with_y(1000.).with_x(500.),
+            Terrain::with_default_limits(vec![0., 1000.], vec![0., 0.]),
+            &PhysicsSettings {
+                power_max: 3,
+                angle_limit: 45.,
+                ..Default::default()
+            },
+            &LandingCriteria::default(),
+            commands,
+            1000,
+        )
+    };
+    for invalid in [
+        Command::new(0., 4),
+        Command::new(60., 2),
+        Command::new(0., -1),
+    ] {
+        assert!(matches!(
+            replay(&[Command::new(0., 3), invalid]),
+            Err(Error::Simulation(SimulationError::InvalidThrust(_)))
+        ));
+    }
+    assert!(replay(&[Command::new(45., 3), Command::new(0., 0)]).is_ok());
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test(flavor = "current_thread")]
+async fn generations_streamed() {
+    use futures_core::Stream;
+    use simulation::GenerationStream;
 
-    for i in 0..10 {
-        let print_err = |e: &String| println!("Failed on {i} iteration: {e}");
-        app.run().inspect_err(print_err).unwrap();
-        app.next_population().inspect_err(print_err).unwrap();
+    let mut generations = GenerationStream::new(simple_app()).with_best_chromosome();
+    // the ticker only advances if polling the stream doesn't block the single-threaded runtime
+    let ticks = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let ticker = tokio::spawn({
+        let ticks = ticks.clone();
+        async move {
+            loop {
+                ticks.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+            }
+        }
+    });
+    for id in 0..2 {
+        let generation =
+            std::future::poll_fn(|cx| std::pin::Pin::new(&mut generations).poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(generation.report.generation, id);
+        assert!(generation.best_chromosome.is_some());
     }
+    ticker.abort();
+    assert!(ticks.load(std::sync::atomic::Ordering::Relaxed) > 1);
+    assert_eq!(generations.into_app().get_population_id(), 2);
 }